@@ -8,7 +8,7 @@ use std::fmt::{Debug, Display};
 use std::fs;
 use std::io::Read;
 use test::Bencher;
-use csv::Csv;
+use csv::{Csv, CsvBuilder};
 
 static CSV_DATA: &'static str = "./examples/data/bench.csv";
 
@@ -37,6 +37,20 @@ fn str_records(b: &mut Bencher) {
     })
 }
 
+#[bench]
+fn repeated_columns(b: &mut Bencher) {
+    let data = file_to_mem(CSV_DATA);
+    let rows = Csv::from_reader(&*data).map(|r| r.unwrap()).collect::<Vec<_>>();
+    b.bytes = data.len() as u64 * 10;
+    b.iter(|| {
+        for row in &rows {
+            for _ in 0..10 {
+                let _ = row.columns().unwrap().count();
+            }
+        }
+    })
+}
+
 #[bench]
 fn bytes_records(b: &mut Bencher) {
     let data = file_to_mem(CSV_DATA);
@@ -50,6 +64,97 @@ fn bytes_records(b: &mut Bencher) {
         }
     })
 }
+
+#[bench]
+fn count_records(b: &mut Bencher) {
+    let data = file_to_mem(CSV_DATA);
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let mut dec = Csv::from_reader(&*data);
+        let _ = dec.count_records().unwrap();
+    })
+}
+
+#[bench]
+fn borrowed_records(b: &mut Bencher) {
+    let data = file_to_mem(CSV_DATA);
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let mut dec = Csv::from_reader(&*data);
+        while let Some(record) = dec.next_borrowed() {
+            for c in record.unwrap() {
+                let _ = c;
+            }
+        }
+    })
+}
+
+#[bench]
+fn byte_record(b: &mut Bencher) {
+    let data = file_to_mem(CSV_DATA);
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let mut dec = Csv::from_reader(&*data);
+        let mut record = csv::ByteRecord::new();
+        while dec.read_byte_record(&mut record).unwrap() {
+            for c in &record {
+                let _ = c;
+            }
+        }
+    })
+}
+
+#[bench]
+fn nth_record(b: &mut Bencher) {
+    let data = file_to_mem(CSV_DATA);
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let mut dec = CsvBuilder::new().flexible(true).build(&*data);
+        let _ = dec.nth(1000);
+    })
+}
+
+#[bench]
+fn nth_record_parsed(b: &mut Bencher) {
+    let data = file_to_mem(CSV_DATA);
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let mut dec = CsvBuilder::new().flexible(true).build(&*data);
+        for _ in 0..1000 {
+            let _ = dec.next();
+        }
+        let _ = dec.next();
+    })
+}
+
+fn wide_data() -> Vec<u8> {
+    let row = (0..200).map(|i| format!("column{}", i)).collect::<Vec<_>>().join(",");
+    (0..1000).map(|_| &*row).collect::<Vec<_>>().join("\n").into_bytes()
+}
+
+#[bench]
+fn wide_records(b: &mut Bencher) {
+    let data = wide_data();
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let dec = Csv::from_reader(&*data);
+        for row in dec.into_iter() {
+            let _ = row.unwrap();
+        }
+    })
+}
+
+#[bench]
+fn wide_records_capacity(b: &mut Bencher) {
+    let data = wide_data();
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let dec = CsvBuilder::new().record_capacity(2048, 200).build(&*data);
+        for row in dec.into_iter() {
+            let _ = row.unwrap();
+        }
+    })
+}
 //
 //#[bench]
 //fn byte_records(b: &mut Bencher) {
@@ -100,3 +205,33 @@ fn decoded_records(b: &mut Bencher) {
         }
     })
 }
+
+#[allow(dead_code)]
+#[derive(RustcDecodable)]
+struct PlayText {
+    gameid: String,
+    qtr: String,
+    min: String,
+    sec: String,
+    team_off: String,
+    team_def: String,
+    down: String,
+    togo: String,
+    ydline: String,
+    description: String,
+    offscore: String,
+    defscore: String,
+    season: String,
+}
+
+#[bench]
+fn decoded_string_records(b: &mut Bencher) {
+    let data = file_to_mem(CSV_DATA);
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let dec = CsvBuilder::new().has_header(true).build(&*data);
+        for row in dec {
+            let _ = row.unwrap().decode::<PlayText>().unwrap();
+        }
+    })
+}
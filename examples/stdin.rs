@@ -0,0 +1,26 @@
+//! Counts rows and columns of a csv piped on standard input
+//!
+//! ```sh
+//! cat examples/data/bench.csv | cargo run --example stdin
+//! ```
+
+extern crate quick_csv;
+
+use quick_csv::CsvBuilder;
+
+fn main() {
+    let (mut rows, mut columns) = (0, 0);
+    for row in CsvBuilder::new().flexible(true).from_stdin() {
+        match row {
+            Ok(row) => {
+                rows += 1;
+                columns += row.len();
+            }
+            Err(e) => {
+                println!("cannot read row {}: {}", rows + 1, e);
+                return;
+            }
+        }
+    }
+    println!("{} rows, {} columns", rows, columns);
+}
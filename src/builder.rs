@@ -0,0 +1,630 @@
+//! Csv builder module
+//!
+//! Provides `CsvBuilder`, which holds all the reading settings and builds
+//! `Csv` readers
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, StdinLock};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use columns::DecodeSettings;
+use multi::MultiFileReader;
+use error::{Error, Result};
+use {validate_settings, Csv, ExtraColumns, FieldMap, FieldValidator, NewlineInQuotes, OnError,
+     ProgressCallback, Terminator, Utf8Mode};
+
+/// Csv reader settings
+///
+/// # Example
+///
+/// ```rust
+/// use quick_csv::CsvBuilder;
+///
+/// let data = "name;age\nalice;31\nbob;27";
+/// let csv = CsvBuilder::new().delimiter(b';').has_header(true).from_string(data);
+/// for row in csv {
+///     let (name, age) = row.unwrap().decode::<(String, u8)>().unwrap();
+///     println!("{} is {}", name, age);
+/// }
+/// ```
+pub struct CsvBuilder {
+    /// delimiter
+    delimiter: u8,
+    /// non ASCII delimiter set by `delimiter_char`, reported by `validate`
+    delimiter_char: Option<char>,
+    /// header
+    has_header: bool,
+    /// headers known out of band
+    headers: Option<Vec<String>>,
+    /// flexible column count
+    flexible: bool,
+    /// discard empty lines
+    skip_empty_lines: bool,
+    /// consecutive delimiters considered as one
+    collapse_delimiters: bool,
+    /// drop an unquoted empty last column
+    trim_trailing_empty: bool,
+    /// handling of rows with too many columns
+    extra_columns: ExtraColumns,
+    /// quotes handling
+    quoting: bool,
+    /// line terminator
+    terminator: Terminator,
+    /// handling of `\r\n` within quoted fields
+    newline_in_quotes: NewlineInQuotes,
+    /// handling of invalid UTF-8
+    utf8: Utf8Mode,
+    /// columns returned as they appear in the input
+    raw_fields: bool,
+    /// decoding settings
+    settings: DecodeSettings,
+    /// selected columns indices
+    selection: Option<Vec<usize>>,
+    /// transformation applied to every column
+    field_map: Option<FieldMap>,
+    /// validation of every column, with its index
+    field_validator: Option<FieldValidator>,
+    /// initial line buffer capacity
+    buf_capacity: usize,
+    /// initial column count capacity
+    cols_capacity: usize,
+    /// maximum record size in bytes
+    max_record_size: usize,
+    /// strip a leading UTF-8 BOM
+    skip_bom: bool,
+    /// size of the preamble discarded before the first record
+    skip_bytes: u64,
+    /// data rows skipped after the header
+    skip_rows: usize,
+    /// behavior on malformed rows
+    on_error: OnError,
+    /// progress callback, called every given number of bytes
+    progress: Option<(u64, ProgressCallback)>,
+}
+
+impl Default for CsvBuilder {
+    fn default() -> CsvBuilder {
+        CsvBuilder::new()
+    }
+}
+
+impl CsvBuilder {
+
+    /// Creates a builder with the default settings
+    ///
+    /// Note: default delimiter = ','
+    pub fn new() -> CsvBuilder {
+        CsvBuilder {
+            delimiter: b',',
+            delimiter_char: None,
+            has_header: false,
+            headers: None,
+            flexible: false,
+            skip_empty_lines: false,
+            collapse_delimiters: false,
+            trim_trailing_empty: false,
+            extra_columns: ExtraColumns::Error,
+            quoting: true,
+            terminator: Terminator::Crlf,
+            newline_in_quotes: NewlineInQuotes::Preserve,
+            utf8: Utf8Mode::Strict,
+            raw_fields: false,
+            settings: DecodeSettings::default(),
+            selection: None,
+            field_map: None,
+            field_validator: None,
+            buf_capacity: 0,
+            cols_capacity: 0,
+            max_record_size: usize::MAX,
+            skip_bom: true,
+            skip_bytes: 0,
+            skip_rows: 0,
+            on_error: OnError::Stop,
+            progress: None,
+        }
+    }
+
+    /// Sets a new delimiter
+    pub fn delimiter(mut self, delimiter: u8) -> CsvBuilder {
+        self.delimiter = delimiter;
+        self.delimiter_char = None;
+        self
+    }
+
+    /// Sets a new delimiter from a `char`, e.g. `'\t'`
+    ///
+    /// Only ASCII delimiters are supported, any other one makes the settings
+    /// invalid, see `validate`.
+    pub fn delimiter_char(mut self, delimiter: char) -> CsvBuilder {
+        if delimiter.is_ascii() {
+            self.delimiter(delimiter as u8)
+        } else {
+            self.delimiter_char = Some(delimiter);
+            self
+        }
+    }
+
+    /// Sets the delimiter from the extension of `path`
+    ///
+    /// `\t` for `.tsv` and `.tab`, `;` for `.scsv` and `,` otherwise, the
+    /// extension being compared case insensitively. Calling `delimiter`
+    /// afterwards overrides it.
+    pub fn extension_delimiter<P: AsRef<Path>>(self, path: P) -> CsvBuilder {
+        let delimiter = match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("tsv") || e.eq_ignore_ascii_case("tab") => b'\t',
+            Some(e) if e.eq_ignore_ascii_case("scsv") => b';',
+            _ => b',',
+        };
+        self.delimiter(delimiter)
+    }
+
+    /// Sets the delimiter to a tab and disables quoting, for tab-separated files
+    ///
+    /// Quoting can be enabled again by calling `quoting(true)` afterwards
+    pub fn tsv(self) -> CsvBuilder {
+        self.delimiter(b'\t').quoting(false)
+    }
+
+    /// Defines whether quotes are handled (default: true)
+    ///
+    /// When disabled, quotes are ordinary bytes: columns are split on
+    /// delimiters and line terminators only and are never unquoted
+    pub fn quoting(mut self, quoting: bool) -> CsvBuilder {
+        self.quoting = quoting;
+        self
+    }
+
+    /// Sets the line terminator (default: `Terminator::Crlf`)
+    ///
+    /// Terminators are ignored within quoted fields
+    pub fn terminator(mut self, terminator: Terminator) -> CsvBuilder {
+        self.terminator = terminator;
+        self
+    }
+
+    /// Sets how `\r\n` line breaks within quoted fields are handled
+    /// (default: `NewlineInQuotes::Preserve`)
+    ///
+    /// E.g. `NewlineInQuotes::NormalizeLf` reads `"line1\r\nline2"` as
+    /// `line1\nline2`. Applies even with `raw_fields`.
+    pub fn newline_in_quotes(mut self, newline_in_quotes: NewlineInQuotes) -> CsvBuilder {
+        self.newline_in_quotes = newline_in_quotes;
+        self
+    }
+
+    /// Sets how rows which are not valid UTF-8 are handled
+    /// (default: `Utf8Mode::Strict`)
+    ///
+    /// Rows are checked as soon as they are parsed, before `select` and
+    /// `map_fields`, so e.g. `ByteRecord`s are affected as well.
+    /// `Row::decode_lossy` can still be used on some rows only in `Strict`
+    /// mode.
+    pub fn utf8(mut self, utf8: Utf8Mode) -> CsvBuilder {
+        self.utf8 = utf8;
+        self
+    }
+
+    /// Defines whether columns are returned untouched (default: false)
+    ///
+    /// When enabled, surrounding quotes and doubled quotes escapes are kept
+    pub fn raw_fields(mut self, raw_fields: bool) -> CsvBuilder {
+        self.raw_fields = raw_fields;
+        self
+    }
+
+    /// Sets additional tokens decoded as booleans, e.g. `yes`/`no`
+    ///
+    /// `true` and `false` are always accepted
+    pub fn bool_tokens(mut self, true_tokens: &[&str], false_tokens: &[&str]) -> CsvBuilder {
+        self.settings.true_tokens = true_tokens.iter().map(|t| t.to_string()).collect();
+        self.settings.false_tokens = false_tokens.iter().map(|t| t.to_string()).collect();
+        self
+    }
+
+    /// Sets additional tokens decoded as infinity, negative infinity and NaN
+    /// floats, e.g. `Inf`, `-Infinity` or `NA`
+    ///
+    /// Tokens are compared case insensitively. Rust's own `inf`, `-inf`,
+    /// `infinity` and `NaN` are always accepted. Missing values such as
+    /// `#N/A` are better handled with `null_tokens`
+    pub fn float_tokens(mut self, infinity: &[&str], neg_infinity: &[&str], nan: &[&str])
+        -> CsvBuilder
+    {
+        self.settings.infinity_tokens = infinity.iter().map(|t| t.to_string()).collect();
+        self.settings.neg_infinity_tokens = neg_infinity.iter().map(|t| t.to_string()).collect();
+        self.settings.nan_tokens = nan.iter().map(|t| t.to_string()).collect();
+        self
+    }
+
+    /// Sets additional tokens decoded as `None`, e.g. `NA` or `\N`
+    ///
+    /// Empty columns are always decoded as `None`
+    pub fn null_tokens(mut self, null_tokens: Vec<String>) -> CsvBuilder {
+        self.settings.null_tokens = null_tokens;
+        self
+    }
+
+    /// Defines whether null tokens are compared case insensitively (default: false)
+    pub fn null_ignore_case(mut self, ignore_case: bool) -> CsvBuilder {
+        self.settings.null_ignore_case = ignore_case;
+        self
+    }
+
+    /// Sets the decimal separator of floats (default: `b'.'`)
+    ///
+    /// E.g. `b','` to decode `3,14` as `3.14`
+    pub fn decimal_separator(mut self, separator: u8) -> CsvBuilder {
+        self.settings.decimal_separator = match separator {
+            b'.' => None,
+            s => Some(s),
+        };
+        self
+    }
+
+    /// Sets a thousands separator, removed before decoding numbers (default: none)
+    ///
+    /// E.g. `b'.'`, along with `decimal_separator(b',')`, to decode
+    /// `1.234,56` as `1234.56`
+    pub fn thousands_separator(mut self, separator: u8) -> CsvBuilder {
+        self.settings.thousands_separator = Some(separator);
+        self
+    }
+
+    /// Sets the radix of integers (default: 10, using `FromStr`)
+    ///
+    /// Once set, integers may also be prefixed with `0x`, `0o` or `0b`,
+    /// which take precedence over `radix`. A radix which is not in the range
+    /// from 2 to 36 makes the settings invalid, see `validate`.
+    pub fn integer_radix(mut self, radix: u32) -> CsvBuilder {
+        self.settings.integer_radix = Some(radix);
+        self
+    }
+
+    /// Defines whether enum variants are matched leniently (default: false)
+    ///
+    /// Variants are then also matched case insensitively, e.g. `enum1` for
+    /// `Enum1`, or by their index, e.g. `1` for the second variant. An exact
+    /// name match always takes precedence.
+    pub fn lenient_enums(mut self, lenient_enums: bool) -> CsvBuilder {
+        self.settings.lenient_enums = lenient_enums;
+        self
+    }
+
+    /// Sets the `chrono` format of a date column, e.g. `"%d/%m/%Y"`
+    ///
+    /// `column` is the index of the column in the decoded row, i.e. after any
+    /// selection. It is used by `Columns::read_naive_date` and
+    /// `Columns::read_naive_datetime`, which parse the value with
+    /// `parse_from_str`. An invalid date is an `Error::ParseField`.
+    ///
+    /// Requires the `chrono` feature
+    #[cfg(feature = "chrono")]
+    pub fn date_format(mut self, column: usize, format: &str) -> CsvBuilder {
+        self.settings.date_formats.retain(|&(c, _)| c != column);
+        self.settings.date_formats.push((column, format.to_string()));
+        self
+    }
+
+    /// Defines whether missing trailing columns are decoded as empty columns
+    /// (default: false)
+    ///
+    /// Useful with `flexible(true)`: when decoding a tuple or a struct, short
+    /// rows are completed with empty columns, decoded as `""` or `None`
+    pub fn fill_missing(mut self, fill_missing: bool) -> CsvBuilder {
+        self.settings.fill_missing = fill_missing;
+        self
+    }
+
+    /// Applies `f` to every column of the data rows, quotes being already
+    /// stripped
+    ///
+    /// The header row is left unchanged, as are the columns which are not
+    /// valid UTF-8. This is opt-in
+    /// as every row is then copied into a new buffer, whether `f` modifies
+    /// it or not.
+    pub fn map_fields<F>(mut self, f: F) -> CsvBuilder
+        where F: Fn(&str) -> Cow<str> + Send + Sync + 'static
+    {
+        self.field_map = Some(Box::new(f));
+        self
+    }
+
+    /// Validates every column with `f`, called with the column index and
+    /// content
+    ///
+    /// Validation runs on all the columns of the data rows, before `select`
+    /// and `map_fields`, even on columns which are not decoded later. The
+    /// header row is not validated.
+    /// Errors are yielded as `Error::InvalidField`, along with the line
+    /// number, and handled as malformed rows as per `on_error`.
+    pub fn validate_field<F>(mut self, f: F) -> CsvBuilder
+        where F: Fn(usize, &str) -> Result<()> + Send + Sync + 'static
+    {
+        self.field_validator = Some(Box::new(f));
+        self
+    }
+
+    /// Selects a subset of columns, in the given order
+    ///
+    /// Rows, and the header, only expose the selected columns. A row missing
+    /// any of the selected columns is a malformed row, yielded as an
+    /// `Error::ColumnMismatch` and handled as per `on_error`.
+    pub fn select(mut self, indices: Vec<usize>) -> CsvBuilder {
+        self.selection = Some(indices);
+        self
+    }
+
+    /// Sets flexible columns
+    pub fn flexible(mut self, flexible: bool) -> CsvBuilder {
+        self.flexible = flexible;
+        self
+    }
+
+    /// Sets the initial capacity of each row, in bytes and in columns
+    ///
+    /// Avoids reallocations on the first rows of wide files. Once the
+    /// column count is known, it is used as columns capacity instead
+    pub fn record_capacity(mut self, bytes: usize, fields: usize) -> CsvBuilder {
+        self.buf_capacity = bytes;
+        self.cols_capacity = fields;
+        self
+    }
+
+    /// Sets the maximum size of a record, in bytes (default: unlimited)
+    ///
+    /// Guards against unterminated quotes on untrusted inputs, which would
+    /// otherwise buffer the rest of the input as a single record. Larger
+    /// records are reported as `Error::RecordTooLarge` and the iteration
+    /// stops. The limit is checked each time the reader buffer is exhausted,
+    /// so up to one reader buffer more may be allocated.
+    pub fn max_record_size(mut self, max_record_size: usize) -> CsvBuilder {
+        self.max_record_size = max_record_size;
+        self
+    }
+
+    /// Defines what to do when a row is malformed (default: `OnError::Stop`)
+    pub fn on_error(mut self, on_error: OnError) -> CsvBuilder {
+        self.on_error = on_error;
+        self
+    }
+
+    /// Calls `f` with the number of bytes consumed so far, roughly every
+    /// `every_bytes` bytes
+    ///
+    /// `f` is called at most once per record read, once the record crosses
+    /// a multiple of `every_bytes`, which is hence only approximate for
+    /// records larger than it. `every_bytes` of 0 is considered as 1.
+    pub fn on_progress<F>(mut self, every_bytes: u64, f: F) -> CsvBuilder
+        where F: FnMut(u64) + Send + 'static
+    {
+        self.progress = Some((::std::cmp::max(every_bytes, 1), Box::new(f)));
+        self
+    }
+
+    /// Defines whether a leading UTF-8 BOM is discarded (default: true)
+    pub fn skip_bom(mut self, skip_bom: bool) -> CsvBuilder {
+        self.skip_bom = skip_bom;
+        self
+    }
+
+    /// Sets the number of bytes discarded before the first record, e.g. a
+    /// fixed size preamble which is not line structured (default: 0)
+    ///
+    /// The preamble comes before the header row and any BOM, and is counted
+    /// by `Csv::bytes_read`. A shorter input is read as an empty one.
+    pub fn skip_bytes(mut self, n: u64) -> CsvBuilder {
+        self.skip_bytes = n;
+        self
+    }
+
+    /// Skips the `n` first data rows (default: 0)
+    ///
+    /// Unlike `Iterator::skip`, the header row is never counted. Rows are
+    /// skipped by the first reading method, as per `Iterator::nth`: a
+    /// malformed skipped row is not reported, unless its quote is left open
+    /// until the end of the input.
+    pub fn skip_rows(mut self, n: usize) -> CsvBuilder {
+        self.skip_rows = n;
+        self
+    }
+
+    /// Defines whether empty lines are discarded (default: false)
+    ///
+    /// When disabled, empty lines are rows with a single empty column, e.g.
+    /// `"\n"` is one row while `""` has no row. A quoted empty column, `""`,
+    /// is never an empty line. `Csv::bytes_read` tells an empty input from
+    /// a blank one.
+    pub fn skip_empty_lines(mut self, skip_empty_lines: bool) -> CsvBuilder {
+        self.skip_empty_lines = skip_empty_lines;
+        self
+    }
+
+    /// Splits columns on runs of spaces (default: false)
+    ///
+    /// Leading and trailing delimiters are ignored and consecutive ones
+    /// are considered as a single delimiter. Use `delimiter` afterwards to
+    /// collapse another byte, e.g. `b'\t'`.
+    ///
+    /// Quoting still applies: a quoted field, even `""`, is always a column.
+    /// The column count checked when not `flexible` is the collapsed one.
+    pub fn whitespace_delimited(mut self, whitespace_delimited: bool) -> CsvBuilder {
+        if whitespace_delimited {
+            self.delimiter = b' ';
+        }
+        self.collapse_delimiters = whitespace_delimited;
+        self
+    }
+
+    /// Defines whether an empty last column is dropped (default: false)
+    ///
+    /// Useful for exports ending every line with a delimiter, e.g. `a,b,`
+    /// is read as 2 columns. A quoted empty last column, as in `a,b,""`, is
+    /// kept. Columns are counted after trimming.
+    pub fn trim_trailing_empty(mut self, trim_trailing_empty: bool) -> CsvBuilder {
+        self.trim_trailing_empty = trim_trailing_empty;
+        self
+    }
+
+    /// Defines how rows with more columns than the first row, or the header,
+    /// are handled (default: `ExtraColumns::Error`)
+    ///
+    /// Ignored when `flexible`. Rows with fewer columns are still errors.
+    pub fn extra_columns(mut self, extra_columns: ExtraColumns) -> CsvBuilder {
+        self.extra_columns = extra_columns;
+        self
+    }
+
+    /// Defines whether the first row is a header (default: false)
+    ///
+    /// The header row is not read when the `Csv` is built but by the first
+    /// call to `next`, `headers` or any other reading method
+    pub fn has_header(mut self, has_header: bool) -> CsvBuilder {
+        self.has_header = has_header;
+        self
+    }
+
+    /// Sets the headers without reading any row
+    ///
+    /// Useful when headers are known out of band: unless `has_header` is
+    /// set, the first row is still yielded by the iterator
+    pub fn set_headers(mut self, headers: Vec<String>) -> CsvBuilder {
+        self.headers = Some(headers);
+        self
+    }
+
+    /// Checks that the settings are consistent
+    ///
+    /// Only the following conflicts are detected:
+    /// - a delimiter set by `delimiter_char` which is not ASCII
+    /// - a delimiter, quote (if `quoting`) or terminator byte used twice
+    /// - the same decimal and thousands separators
+    /// - an integer radix which is not in the range from 2 to 36
+    ///
+    /// Settings depending on the input, e.g. `select` indices out of range,
+    /// are only reported when reading the rows.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(c) = self.delimiter_char {
+            return Err(Error::InvalidConfig(format!(
+                "the delimiter '{}' is not an ASCII character", c)));
+        }
+        validate_settings(self.delimiter, self.quoting, &self.terminator, &self.settings)
+    }
+
+    /// Builds a `Csv` reading from a generic `BufRead`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings are inconsistent, see `validate`
+    pub fn build<B: BufRead>(self, reader: B) -> Csv<B> {
+        match self.try_build(reader) {
+            Ok(csv) => csv,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Builds a `Csv` reading from a generic `BufRead`, or an
+    /// `Error::InvalidConfig` if the settings are inconsistent
+    pub fn try_build<B: BufRead>(self, reader: B) -> Result<Csv<B>> {
+        try!(self.validate());
+        Ok(self.apply(Csv::from_reader(reader)))
+    }
+
+    /// Builds a `Csv` reading from a file path
+    ///
+    /// Inconsistent settings are returned as an `Error::InvalidConfig`
+    pub fn from_file<P: AsRef<Path>>(self, path: P) -> Result<Csv<BufReader<File>>> {
+        try!(self.validate());
+        Csv::from_file(path).map(|csv| self.apply(csv))
+    }
+
+    /// Builds a `Csv` reading several files one after the other, as a
+    /// single csv
+    ///
+    /// If `has_header` is set, the header is read from the first non empty
+    /// file and the first line of the other files is dropped. Dropped lines
+    /// are not counted by `Csv::current_line` nor `Csv::bytes_read`, see
+    /// `Csv::from_files` for the other counters. Inconsistent settings are
+    /// returned as an `Error::InvalidConfig`.
+    ///
+    /// Build a `MultiFileReader` to drop, or keep, the header lines
+    /// regardless of `has_header`.
+    pub fn from_files<P: AsRef<Path>>(self, paths: &[P]) -> Result<Csv<MultiFileReader>> {
+        try!(self.validate());
+        let skip_headers = self.has_header;
+        let terminator = self.terminator.clone();
+        let reader = MultiFileReader::new(paths, skip_headers).terminator(terminator);
+        let total_bytes = try!(reader.max_len());
+        let mut csv = Csv::from_reader(reader);
+        csv.total_bytes = total_bytes;
+        Ok(self.apply(csv))
+    }
+
+    /// Builds a `Csv` reading from a gzip compressed file path
+    ///
+    /// Requires the `flate2` feature
+    #[cfg(feature = "flate2")]
+    pub fn from_gzip_file<P: AsRef<Path>>(self, path: P)
+        -> Result<Csv<BufReader<::flate2::read::GzDecoder<File>>>>
+    {
+        try!(self.validate());
+        Csv::from_gzip_file(path).map(|csv| self.apply(csv))
+    }
+
+    /// Builds a `Csv` reading from the locked standard input
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings are inconsistent, see `validate`
+    pub fn from_stdin(self) -> Csv<StdinLock<'static>> {
+        self.build(io::stdin().lock())
+    }
+
+    /// Builds a `Csv` reading from an in memory string buffer
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings are inconsistent, see `validate`
+    pub fn from_string(self, s: &str) -> Csv<&[u8]> {
+        if let Err(e) = self.validate() {
+            panic!("{}", e);
+        }
+        self.apply(Csv::from_string(s))
+    }
+
+    /// Moves the settings into `csv`
+    fn apply<B: BufRead>(self, mut csv: Csv<B>) -> Csv<B> {
+        csv.delimiter = self.delimiter;
+        csv.has_header = self.has_header;
+        csv.header_pending = self.has_header;
+        csv.headers_given = self.headers.is_some();
+        csv.headers = self.headers;
+        csv.flexible = self.flexible;
+        csv.skip_empty_lines = self.skip_empty_lines;
+        csv.collapse_delimiters = self.collapse_delimiters;
+        csv.trim_trailing_empty = self.trim_trailing_empty;
+        csv.extra_columns = self.extra_columns;
+        csv.quoting = self.quoting;
+        csv.terminator = self.terminator;
+        csv.newline_in_quotes = self.newline_in_quotes;
+        csv.utf8 = self.utf8;
+        csv.raw_fields = self.raw_fields;
+        csv.settings = Arc::new(self.settings);
+        csv.selection = self.selection;
+        csv.field_map = self.field_map;
+        csv.field_validator = self.field_validator;
+        csv.buf_capacity = self.buf_capacity;
+        csv.cols_capacity = self.cols_capacity;
+        csv.max_record_size = self.max_record_size;
+        csv.skip_bom = self.skip_bom;
+        csv.skip_bytes = self.skip_bytes;
+        csv.skip_rows = self.skip_rows;
+        csv.skip_rows_pending = self.skip_rows > 0;
+        csv.on_error = self.on_error;
+        csv.next_progress = self.progress.as_ref().map_or(0, |p| p.0);
+        csv.progress = self.progress.map(|(every, f)| (every, Mutex::new(f)));
+        csv
+    }
+
+}
@@ -3,16 +3,152 @@
 //! Provides convenient or fast column conversions
 //! Deserialize columns into a `Decodable` type
 
+use std::borrow::Cow;
+use std::num::ParseIntError;
 use std::str::FromStr;
 use rustc_serialize as serialize;
-use error::{Result, Error};
+use error::{Result, Error, FieldErrorKind};
 use std::slice::Iter;
+#[cfg(feature = "chrono")]
+use chrono::{NaiveDate, NaiveDateTime, ParseResult};
+
+/// Default decoding settings
+static DEFAULT_SETTINGS: DecodeSettings = DecodeSettings {
+    true_tokens: Vec::new(),
+    false_tokens: Vec::new(),
+    null_tokens: Vec::new(),
+    null_ignore_case: false,
+    fill_missing: false,
+    decimal_separator: None,
+    thousands_separator: None,
+    integer_radix: None,
+    infinity_tokens: Vec::new(),
+    neg_infinity_tokens: Vec::new(),
+    nan_tokens: Vec::new(),
+    lenient_enums: false,
+    #[cfg(feature = "chrono")]
+    date_formats: Vec::new(),
+};
+
+/// Decoding settings, shared by all rows of a `Csv`
+#[derive(Debug, Clone, Default)]
+pub struct DecodeSettings {
+    /// additional tokens decoded as `true`
+    pub true_tokens: Vec<String>,
+    /// additional tokens decoded as `false`
+    pub false_tokens: Vec<String>,
+    /// additional tokens decoded as `None`
+    pub null_tokens: Vec<String>,
+    /// whether `null_tokens` are compared case insensitively
+    pub null_ignore_case: bool,
+    /// whether missing trailing columns of tuples and structs are decoded
+    /// as empty columns
+    pub fill_missing: bool,
+    /// decimal separator of floats if not `.`, e.g. `,` in european locales
+    pub decimal_separator: Option<u8>,
+    /// thousands separator removed from numbers, e.g. `.` in `1.234,56`
+    pub thousands_separator: Option<u8>,
+    /// radix of integers, a `0x`, `0o` or `0b` prefix taking precedence
+    pub integer_radix: Option<u32>,
+    /// additional tokens decoded as infinity, compared case insensitively
+    pub infinity_tokens: Vec<String>,
+    /// additional tokens decoded as negative infinity, compared case insensitively
+    pub neg_infinity_tokens: Vec<String>,
+    /// additional tokens decoded as NaN, compared case insensitively
+    pub nan_tokens: Vec<String>,
+    /// whether enum variants are also matched case insensitively or by index
+    pub lenient_enums: bool,
+    /// `chrono` formats of date columns, by column index
+    #[cfg(feature = "chrono")]
+    pub date_formats: Vec<(usize, String)>,
+}
+
+impl DecodeSettings {
+
+    /// Checks if a column should be decoded as `None`
+    pub fn is_null(&self, col: &str) -> bool {
+        col.is_empty() || self.null_tokens.iter().any(|n| {
+            if self.null_ignore_case { n.eq_ignore_ascii_case(col) } else { n == col }
+        })
+    }
+
+    /// Gets the index of the enum variant named `col` in `names`
+    ///
+    /// An exact match takes precedence over lenient ones
+    fn variant_index(&self, names: &[&str], col: &str) -> Option<usize> {
+        names.iter().position(|&n| n == col).or_else(|| {
+            if !self.lenient_enums {
+                return None;
+            }
+            names.iter().position(|n| n.eq_ignore_ascii_case(col))
+                .or_else(|| col.parse::<usize>().ok().and_then(|i| {
+                    if i < names.len() { Some(i) } else { None }
+                }))
+        })
+    }
+
+    /// Gets the `FromStr` representation of special float tokens
+    fn float_token(&self, col: &str) -> Option<&'static str> {
+        let is = |tokens: &[String]| tokens.iter().any(|t| t.eq_ignore_ascii_case(col));
+        if is(&self.infinity_tokens) {
+            Some("inf")
+        } else if is(&self.neg_infinity_tokens) {
+            Some("-inf")
+        } else if is(&self.nan_tokens) {
+            Some("NaN")
+        } else {
+            None
+        }
+    }
+
+    /// Converts a number to the format expected by `FromStr`, removing
+    /// thousands separators and replacing the decimal separator with `.`
+    /// if `float`
+    pub fn normalize_number<'a>(&self, col: &'a str, float: bool) -> Cow<'a, str> {
+        let decimal = if float { self.decimal_separator } else { None };
+        let thousands = self.thousands_separator;
+        if decimal.is_none() && thousands.is_none() {
+            return Cow::Borrowed(col);
+        }
+        let mut number = String::with_capacity(col.len());
+        for c in col.chars() {
+            if Some(c) == thousands.map(|t| t as char) {
+                continue;
+            } else if Some(c) == decimal.map(|d| d as char) {
+                number.push('.');
+            } else {
+                number.push(c);
+            }
+        }
+        Cow::Owned(number)
+    }
+
+}
+
+/// Integers which can be parsed in any radix
+trait FromStrRadix: Sized {
+    fn from_str_radix(s: &str, radix: u32) -> ::std::result::Result<Self, ParseIntError>;
+}
+
+macro_rules! impl_from_str_radix {
+    ($($t: ty),*) => {
+        $(impl FromStrRadix for $t {
+            fn from_str_radix(s: &str, radix: u32) -> ::std::result::Result<$t, ParseIntError> {
+                <$t>::from_str_radix(s, radix)
+            }
+        })*
+    }
+}
+
+impl_from_str_radix!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
 
 /// Iterator over bytes slice of columns
+#[derive(Clone)]
 pub struct BytesColumns<'a> {
     pos: usize,
     line: &'a [u8],
     iter: Iter<'a, usize>,
+    unquote: bool,
 }
 
 impl<'a> Iterator for BytesColumns<'a> {
@@ -22,7 +158,11 @@ impl<'a> Iterator for BytesColumns<'a> {
         self.iter.next().map(|p| {
             let s = &self.line[self.pos..*p];
             self.pos = *p + 1;
-            if s.starts_with(&[b'\"']) { &s[1..s.len() - 1] } else { s }
+            if self.unquote && s.len() > 1 && s.starts_with(&[b'\"']) && s.ends_with(&[b'\"']) {
+                &s[1..s.len() - 1]
+            } else {
+                s
+            }
         })
     }
 
@@ -46,26 +186,45 @@ impl<'a> BytesColumns<'a> {
             pos: 0,
             line: line,
             iter: cols.iter(),
+            unquote: true,
         }
     }
 
+    /// Sets whether surrounding quotes are stripped from columns (default: true)
+    pub fn unquote(mut self, unquote: bool) -> BytesColumns<'a> {
+        self.unquote = unquote;
+        self
+    }
+
+    /// Gets the remaining columns as a single slice, from the next column
+    /// to the end of the line
+    pub fn as_slice(&self) -> &'a [u8] {
+        &self.line[::std::cmp::min(self.pos, self.line.len())..]
+    }
+
 }
 
-/// &str iterator on columns
-pub struct Columns<'a> {
+/// &str iterator on columns, along with whether they were quoted
+#[derive(Clone)]
+pub struct QuotedColumns<'a> {
     pos: usize,
     line: &'a str,
     iter: Iter<'a, usize>,
+    unquote: bool,
 }
 
-impl<'a> Iterator for Columns<'a> {
-    type Item = &'a str;
+impl<'a> Iterator for QuotedColumns<'a> {
+    type Item = (&'a str, bool);
 
-    fn next(&mut self) -> Option<&'a str> {
+    fn next(&mut self) -> Option<(&'a str, bool)> {
         self.iter.next().map(|p| {
             let s = &self.line[self.pos..*p];
             self.pos = *p + 1;
-            if s.starts_with('\"') { &s[1..s.len() - 1] } else { s }
+            if self.unquote && s.len() > 1 && s.starts_with('\"') && s.ends_with('\"') {
+                (&s[1..s.len() - 1], true)
+            } else {
+                (s, false)
+            }
         })
     }
 
@@ -75,12 +234,83 @@ impl<'a> Iterator for Columns<'a> {
 
 }
 
-impl<'a> ExactSizeIterator for Columns<'a> {
+impl<'a> ExactSizeIterator for QuotedColumns<'a> {
     fn len(&self) -> usize {
         self.iter.len()
     }
 }
 
+impl<'a> QuotedColumns<'a> {
+
+    /// Creates a new QuotedColumns iterator
+    pub fn new(line: &'a str, cols: &'a [usize]) -> QuotedColumns<'a> {
+        QuotedColumns {
+            pos: 0,
+            line: line,
+            iter: cols.iter(),
+            unquote: true,
+        }
+    }
+
+    /// Sets whether surrounding quotes are stripped from columns (default: true)
+    ///
+    /// Columns are never considered as quoted when quotes are not stripped
+    pub fn unquote(mut self, unquote: bool) -> QuotedColumns<'a> {
+        self.unquote = unquote;
+        self
+    }
+
+}
+
+/// &str iterator on columns
+#[derive(Clone)]
+pub struct Columns<'a> {
+    pos: usize,
+    line: &'a str,
+    iter: Iter<'a, usize>,
+    unquote: bool,
+    settings: &'a DecodeSettings,
+    missing: usize,
+    consumed: usize,
+}
+
+impl<'a> Iterator for Columns<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let col = match self.iter.next() {
+            Some(p) => {
+                let s = &self.line[self.pos..*p];
+                self.pos = *p + 1;
+                if self.unquote && s.len() > 1 && s.starts_with('\"') && s.ends_with('\"') {
+                    &s[1..s.len() - 1]
+                } else {
+                    s
+                }
+            }
+            None if self.missing > 0 => {
+                self.missing -= 1;
+                ""
+            }
+            None => return None,
+        };
+        self.consumed += 1;
+        Some(col)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+
+}
+
+impl<'a> ExactSizeIterator for Columns<'a> {
+    fn len(&self) -> usize {
+        self.iter.len() + self.missing
+    }
+}
+
 impl<'a> Columns<'a> {
 
     /// Creates a new Columns iterator
@@ -89,24 +319,168 @@ impl<'a> Columns<'a> {
             pos: 0,
             line: line,
             iter: cols.iter(),
+            unquote: true,
+            settings: &DEFAULT_SETTINGS,
+            missing: 0,
+            consumed: 0,
         }
     }
 
+    /// Sets whether surrounding quotes are stripped from columns (default: true)
+    pub fn unquote(mut self, unquote: bool) -> Columns<'a> {
+        self.unquote = unquote;
+        self
+    }
+
+    /// Sets the settings used when decoding
+    pub fn settings(mut self, settings: &'a DecodeSettings) -> Columns<'a> {
+        self.settings = settings;
+        self
+    }
+
+    /// Gets the remaining columns as a single string, from the next column
+    /// to the end of the line
+    ///
+    /// Delimiters and surrounding quotes are kept, doubled quotes are
+    /// unescaped as in `Row::as_str`
+    pub fn as_slice(&self) -> &'a str {
+        &self.line[::std::cmp::min(self.pos, self.line.len())..]
+    }
+
     fn peek(&self) -> Option<&'a str> {
-        self.iter.clone().next().map(|p| {
-            let s = &self.line[self.pos..*p];
-            if s.starts_with('\"') { &s[1..s.len() - 1] } else { s }
-        })
+        match self.iter.clone().next() {
+            Some(p) => {
+                let s = &self.line[self.pos..*p];
+                Some(if self.unquote && s.len() > 1 && s.starts_with('\"') && s.ends_with('\"') {
+                    &s[1..s.len() - 1]
+                } else {
+                    s
+                })
+            }
+            None if self.missing > 0 => Some(""),
+            None => None,
+        }
+    }
+
+    /// Expects `len` more columns, filling missing ones with empty columns
+    /// if `fill_missing` is set
+    fn expect(&mut self, len: usize) {
+        if self.settings.fill_missing {
+            self.missing = ::std::cmp::max(self.missing, len.saturating_sub(self.iter.len()));
+        }
     }
 
     fn next_str<T>(&mut self) -> Result<T>
         where T: FromStr + ::std::fmt::Debug, 
-              T::Err: ::std::fmt::Debug
+              T::Err: Into<FieldErrorKind>
+    {
+        self.next().ok_or(Error::EOL).and_then(|col| self.parse(col))
+    }
+
+    /// Reads next column as a float, as per the separators settings
+    fn next_float<T>(&mut self) -> Result<T>
+        where T: FromStr + ::std::fmt::Debug,
+              T::Err: Into<FieldErrorKind>
+    {
+        let col = try!(self.next().ok_or(Error::EOL));
+        if let Some(token) = self.settings.float_token(col) {
+            return self.parse(token);
+        }
+        let number = self.settings.normalize_number(col, true);
+        self.parse(&number)
+    }
+
+    /// Reads next column as an integer, as per the separators and radix settings
+    fn next_integer<T>(&mut self) -> Result<T>
+        where T: FromStr + FromStrRadix + ::std::fmt::Debug,
+              <T as FromStr>::Err: Into<FieldErrorKind>
+    {
+        let col = try!(self.next().ok_or(Error::EOL));
+        let number = self.settings.normalize_number(col, false);
+        let radix = match self.settings.integer_radix {
+            Some(radix) => radix,
+            None => return self.parse(&number),
+        };
+        let (negative, digits) = match number.chars().next() {
+            Some('-') => (true, &number[1..]),
+            Some('+') => (false, &number[1..]),
+            _ => (false, &*number),
+        };
+        let (radix, digits) = match digits.get(..2) {
+            Some("0x") | Some("0X") => (16, &digits[2..]),
+            Some("0o") | Some("0O") => (8, &digits[2..]),
+            Some("0b") | Some("0B") => (2, &digits[2..]),
+            _ => (radix, digits),
+        };
+        let digits = if negative { Cow::Owned(format!("-{}", digits)) } else { Cow::Borrowed(digits) };
+        T::from_str_radix(&digits, radix).map_err(|e| self.field_error(col, e))
+    }
+
+    /// Parses `col`, the last consumed column
+    fn parse<T>(&self, col: &str) -> Result<T>
+        where T: FromStr + ::std::fmt::Debug,
+              T::Err: Into<FieldErrorKind>
     {
-        self.next().ok_or(Error::EOL).and_then(|col|
-            FromStr::from_str(col).map_err(|e|
-                Error::Decode(format!("Failed converting {}th column (\'{}\'):\n\t{:?}", 
-                    self.len(), col, e))))
+        FromStr::from_str(col).map_err(|e| self.field_error(col, e))
+    }
+
+    /// Parses next column with `parse`, as per the `chrono` format set for
+    /// this column or `default`
+    #[cfg(feature = "chrono")]
+    fn next_date<T>(&mut self, default: &str, parse: fn(&str, &str) -> ParseResult<T>)
+        -> Result<T>
+    {
+        let settings = self.settings;
+        let format = settings.date_formats.iter()
+            .find(|&&(c, _)| c == self.consumed)
+            .map_or(default, |&(_, ref f)| &f[..]);
+        let col = try!(self.next().ok_or(Error::EOL));
+        parse(col, format).map_err(|e| self.field_error(col, FieldErrorKind::Other(e.to_string())))
+    }
+
+    /// Builds an `Error::ParseField` for the last consumed column
+    fn field_error<E: Into<FieldErrorKind>>(&self, col: &str, e: E) -> Error {
+        Error::ParseField {
+            column: self.consumed.saturating_sub(1),
+            value: col.to_string(),
+            kind: e.into(),
+        }
+    }
+
+    /// Reads next column as a `u128`
+    ///
+    /// `rustc_serialize::Decoder` has no 128-bit methods so this one is
+    /// provided directly on `Columns`
+    pub fn read_u128(&mut self) -> Result<u128> { self.next_integer() }
+
+    /// Reads next column as an `i128`
+    ///
+    /// `rustc_serialize::Decoder` has no 128-bit methods so this one is
+    /// provided directly on `Columns`
+    pub fn read_i128(&mut self) -> Result<i128> { self.next_integer() }
+
+    /// Reads next column as a `chrono::NaiveDate`
+    ///
+    /// The column is parsed with the format set by `CsvBuilder::date_format`,
+    /// `%Y-%m-%d` by default. `chrono` types are not `Decodable` so this one
+    /// is provided directly on `Columns`
+    ///
+    /// Requires the `chrono` feature
+    #[cfg(feature = "chrono")]
+    pub fn read_naive_date(&mut self) -> Result<NaiveDate> {
+        self.next_date("%Y-%m-%d", NaiveDate::parse_from_str)
+    }
+
+    /// Reads next column as a `chrono::NaiveDateTime`
+    ///
+    /// The column is parsed with the format set by `CsvBuilder::date_format`,
+    /// `%Y-%m-%dT%H:%M:%S%.f` by default. `chrono` types are not `Decodable`
+    /// so this one is provided directly on `Columns`
+    ///
+    /// Requires the `chrono` feature
+    #[cfg(feature = "chrono")]
+    pub fn read_naive_datetime(&mut self) -> Result<NaiveDateTime> {
+        self.next_date("%Y-%m-%dT%H:%M:%S%.f", NaiveDateTime::parse_from_str)
     }
 
     /// Deserializes a Columns iterator into any Decodable type
@@ -122,29 +496,54 @@ impl<'a> serialize::Decoder for Columns<'a> {
     fn error(&mut self, err: &str) -> Error {
         Error::Decode(err.into())
     }
-    fn read_nil(&mut self) -> Result<()> { unimplemented!() }
-    fn read_usize(&mut self) -> Result<usize> { self.next_str() }
-    fn read_u64(&mut self) -> Result<u64> { self.next_str() }
-    fn read_u32(&mut self) -> Result<u32> { self.next_str() }
-    fn read_u16(&mut self) -> Result<u16> { self.next_str() }
-    fn read_u8(&mut self) -> Result<u8> { self.next_str() }
-    fn read_isize(&mut self) -> Result<isize> { self.next_str() }
-    fn read_i64(&mut self) -> Result<i64> { self.next_str() }
-    fn read_i32(&mut self) -> Result<i32> { self.next_str() }
-    fn read_i16(&mut self) -> Result<i16> { self.next_str() }
-    fn read_i8(&mut self) -> Result<i8> { self.next_str() }
-    fn read_bool(&mut self) -> Result<bool> { self.next_str() }
-    fn read_f64(&mut self) -> Result<f64> { self.next_str() }
-    fn read_f32(&mut self) -> Result<f32> { self.next_str() }
+    /// Consumes one column, whatever its content, so `()` can be used as a
+    /// placeholder for an ignored column
+    fn read_nil(&mut self) -> Result<()> {
+        self.next().map(|_| ()).ok_or(Error::EOL)
+    }
+    fn read_usize(&mut self) -> Result<usize> { self.next_integer() }
+    fn read_u64(&mut self) -> Result<u64> { self.next_integer() }
+    fn read_u32(&mut self) -> Result<u32> { self.next_integer() }
+    fn read_u16(&mut self) -> Result<u16> { self.next_integer() }
+    fn read_u8(&mut self) -> Result<u8> { self.next_integer() }
+    fn read_isize(&mut self) -> Result<isize> { self.next_integer() }
+    fn read_i64(&mut self) -> Result<i64> { self.next_integer() }
+    fn read_i32(&mut self) -> Result<i32> { self.next_integer() }
+    fn read_i16(&mut self) -> Result<i16> { self.next_integer() }
+    fn read_i8(&mut self) -> Result<i8> { self.next_integer() }
+    fn read_bool(&mut self) -> Result<bool> {
+        if let Some(col) = self.peek() {
+            let (t, f) = (&self.settings.true_tokens, &self.settings.false_tokens);
+            let value = if t.iter().any(|t| t == col) {
+                Some(true)
+            } else if f.iter().any(|f| f == col) {
+                Some(false)
+            } else {
+                None
+            };
+            if let Some(value) = value {
+                let _ = self.next();
+                return Ok(value);
+            }
+        }
+        self.next_str()
+    }
+    fn read_f64(&mut self) -> Result<f64> { self.next_float() }
+    fn read_f32(&mut self) -> Result<f32> { self.next_float() }
     fn read_char(&mut self) -> Result<char> {
         let col = try!(self.next().ok_or(Error::EOL));
-        if col.len() != 1 {
+        let count = col.chars().count();
+        if count != 1 {
             return Err(Error::Decode(format!(
-                "Expected a single char, found {} chars", col.len())));
+                "Expected a single char in column {}, found {} chars",
+                self.consumed - 1, count)));
         }
         Ok(col.chars().next().unwrap())
     }
-    fn read_str(&mut self) -> Result<String> { self.next_str() }
+    fn read_str(&mut self) -> Result<String> {
+        // infallible, no need for `FromStr`
+        self.next().ok_or(Error::EOL).map(str::to_owned)
+    }
     fn read_enum<T, F>(&mut self, _: &str, f: F) -> Result<T>
             where F: FnOnce(&mut Columns<'a>) -> Result<T> {
         f(self)
@@ -154,9 +553,9 @@ impl<'a> serialize::Decoder for Columns<'a> {
             where F: FnMut(&mut Columns<'a>, usize) -> Result<T> {
         let i = try!(self
             .peek()
-            .and_then(|name| names.iter().position(|&n| n == name))
+            .and_then(|name| self.settings.variant_index(names, name))
             .ok_or(Error::Decode(format!(
-                "Could not load value into any variant in {:?}", names))));
+                "Could not load column {} into any variant in {:?}", self.consumed, names))));
         let _ = self.next();
         f(self, i)
     }
@@ -174,16 +573,18 @@ impl<'a> serialize::Decoder for Columns<'a> {
             where F: FnOnce(&mut Columns<'a>) -> Result<T> {
         self.read_enum_variant_arg(f_idx, f)
     }
-    fn read_struct<T, F>(&mut self, _: &str, _: usize, f: F) -> Result<T>
+    fn read_struct<T, F>(&mut self, _: &str, len: usize, f: F) -> Result<T>
             where F: FnOnce(&mut Columns<'a>) -> Result<T> {
+        self.expect(len);
         f(self)
     }
     fn read_struct_field<T, F>(&mut self, _: &str, _: usize, f: F) -> Result<T>
             where F: FnOnce(&mut Columns<'a>) -> Result<T> {
         f(self)
     }
-    fn read_tuple<T, F>(&mut self, _: usize, f: F) -> Result<T>
+    fn read_tuple<T, F>(&mut self, len: usize, f: F) -> Result<T>
             where F: FnOnce(&mut Columns<'a>) -> Result<T> {
+        self.expect(len);
         f(self)
     }
     fn read_tuple_arg<T, F>(&mut self, _: usize, f: F) -> Result<T>
@@ -202,8 +603,8 @@ impl<'a> serialize::Decoder for Columns<'a> {
     fn read_option<T, F>(&mut self, mut f: F) -> Result<T>
             where F: FnMut(&mut Columns<'a>, bool) -> Result<T> {
         let col = try!(self.peek().ok_or(Error::EOL));
-        if col.is_empty() {
-            let _ = self.iter.next();
+        if self.settings.is_null(col) {
+            let _ = self.next();
             f(self, false)
         } else {
             f(self, true).or_else(|_| f(self, false))
@@ -212,8 +613,15 @@ impl<'a> serialize::Decoder for Columns<'a> {
 
     fn read_seq<T, F>(&mut self, f: F) -> Result<T>
             where F: FnOnce(&mut Columns<'a>, usize) -> Result<T> {
-        let len = self.iter.clone().count();
-        f(self, len)
+        let len = self.len();
+        let consumed = self.consumed;
+        // fixed size arrays need exactly as many columns as left, and fail
+        // before reading any of them otherwise
+        f(self, len).map_err(|e| match e {
+            Error::Decode(msg) if self.consumed == consumed =>
+                Error::Decode(format!("{}, {} columns left", msg, len)),
+            e => e,
+        })
     }
     fn read_seq_elt<T, F>(&mut self, _: usize, f: F) -> Result<T>
             where F: FnOnce(&mut Columns<'a>) -> Result<T> {
@@ -0,0 +1,89 @@
+//! Single-byte encodings module
+//!
+//! Provides `Encoding`, to transcode columns of legacy exports to UTF-8
+//!
+//! Requires the `encoding` feature
+
+use std::borrow::Cow;
+use columns::BytesColumns;
+
+/// Characters of bytes `0x80` to `0x9F` in Windows-1252
+///
+/// Undefined bytes are mapped to the C1 control of the same value, as
+/// browsers do
+static WINDOWS_1252: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+/// Single-byte encoding of a csv
+///
+/// Every byte is a valid character, hence decoding never fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// ISO-8859-1, each byte being the unicode code point of the same value
+    Latin1,
+    /// Windows-1252, Latin-1 with printable characters from `0x80` to `0x9F`
+    Windows1252,
+}
+
+impl Encoding {
+
+    /// Decodes a single byte
+    pub fn decode_byte(self, b: u8) -> char {
+        match self {
+            Encoding::Windows1252 if (0x80..=0x9F).contains(&b) => {
+                WINDOWS_1252[(b - 0x80) as usize]
+            }
+            _ => b as char,
+        }
+    }
+
+    /// Decodes `bytes` to UTF-8, borrowing them if they are ASCII only
+    pub fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+        if bytes.is_ascii() {
+            // ascii is valid UTF-8
+            return Cow::Borrowed(::std::str::from_utf8(bytes).unwrap());
+        }
+        Cow::Owned(bytes.iter().map(|&b| self.decode_byte(b)).collect())
+    }
+
+}
+
+/// Iterator over columns decoded from a single-byte `Encoding`
+#[derive(Clone)]
+pub struct EncodedColumns<'a> {
+    columns: BytesColumns<'a>,
+    encoding: Encoding,
+}
+
+impl<'a> EncodedColumns<'a> {
+    /// Creates a new EncodedColumns iterator
+    pub fn new(columns: BytesColumns<'a>, encoding: Encoding) -> EncodedColumns<'a> {
+        EncodedColumns {
+            columns: columns,
+            encoding: encoding,
+        }
+    }
+}
+
+impl<'a> Iterator for EncodedColumns<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Cow<'a, str>> {
+        let encoding = self.encoding;
+        self.columns.next().map(|c| encoding.decode(c))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.columns.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for EncodedColumns<'a> {
+    fn len(&self) -> usize {
+        self.columns.len()
+    }
+}
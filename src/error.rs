@@ -3,31 +3,134 @@
 //! Provides all csv error conversion and description
 //! Also provides `Result` as a alias of `Result<_, Error>
 
+use std::convert::Infallible;
 use std::fmt;
 use std::io;
+use std::num::{ParseFloatError, ParseIntError};
+use std::str::ParseBoolError;
 
 /// An error produced by an operation on CSV data.
 #[derive(Debug)]
 pub enum Error {
     /// An error reported by the type-based decoder.
     Decode(String),
-    /// An error reported by the CSV parser.
+    /// An error reported by the CSV parser, e.g. a quoted field not
+    /// terminated at the end of the input.
     Parse(String),
     /// An error originating from reading or writing to the underlying buffer.
     Io(io::Error),
     /// An error originating from finding end of line instead of a column.
     EOL,
-    /// Unescaped quote
-    UnescapedQuote,
-    /// Unexpected quote in a column which is non quoted column
+    /// Unescaped quote, e.g. `"ab"cd`
+    UnescapedQuote {
+        /// record content, up to the offending quote at least
+        line: Vec<u8>,
+        /// byte position of the offending quote within `line`
+        pos: usize,
+        /// line number of the record, as per `Csv::current_line`
+        line_number: usize,
+    },
+    /// Unexpected quote in a column which is non quoted column, e.g. `ab"cd`
+    UnexpectedQuote {
+        /// record content, up to the offending quote at least
+        line: Vec<u8>,
+        /// byte position of the offending quote within `line`
+        pos: usize,
+        /// line number of the record, as per `Csv::current_line`
+        line_number: usize,
+    },
+    /// Misspelled alias of `UnexpectedQuote`, never produced by the parser
+    #[deprecated(since = "0.2.0", note = "use UnexpectedQuote")]
     UnexpextedQuote,
     /// Column count mismatch
-    ColumnMismatch(usize, usize),
+    ColumnMismatch {
+        /// column count of the first row, or the header, or the count
+        /// needed by `Csv::select`
+        expected: usize,
+        /// column count of the row
+        got: usize,
+        /// line number of the row, as per `Csv::current_line`
+        line: usize,
+    },
+    /// Record larger than the maximum record size
+    RecordTooLarge(usize),
+    /// A column could not be converted to the expected type
+    ParseField {
+        /// index of the column in the row, starting at 0
+        column: usize,
+        /// column content
+        value: String,
+        /// typed cause of the failure
+        kind: FieldErrorKind,
+    },
+    /// A column rejected by the `Csv::validate_field` validator
+    InvalidField {
+        /// line number
+        line: usize,
+        /// index of the column in the row, starting at 0
+        column: usize,
+        /// error returned by the validator
+        error: Box<Error>,
+    },
+    /// Inconsistent settings, e.g. the same byte used as delimiter and quote
+    InvalidConfig(String),
+}
+
+/// Typed cause of an `Error::ParseField`
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldErrorKind {
+    /// Invalid integer, e.g. not a number or overflowing
+    Int(ParseIntError),
+    /// Invalid float
+    Float(ParseFloatError),
+    /// Invalid boolean
+    Bool(ParseBoolError),
+    /// Any other conversion failure
+    Other(String),
+}
+
+impl fmt::Display for FieldErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FieldErrorKind::Int(ref e) => write!(f, "{}", e),
+            FieldErrorKind::Float(ref e) => write!(f, "{}", e),
+            FieldErrorKind::Bool(ref e) => write!(f, "{}", e),
+            FieldErrorKind::Other(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<ParseIntError> for FieldErrorKind {
+    fn from(e: ParseIntError) -> FieldErrorKind { FieldErrorKind::Int(e) }
+}
+
+impl From<ParseFloatError> for FieldErrorKind {
+    fn from(e: ParseFloatError) -> FieldErrorKind { FieldErrorKind::Float(e) }
+}
+
+impl From<ParseBoolError> for FieldErrorKind {
+    fn from(e: ParseBoolError) -> FieldErrorKind { FieldErrorKind::Bool(e) }
+}
+
+impl From<Infallible> for FieldErrorKind {
+    fn from(e: Infallible) -> FieldErrorKind { match e {} }
+}
+
+/// Number of bytes displayed on each side of an error position
+const SNIPPET_CONTEXT: usize = 20;
+
+/// Gets a short, lossy utf8, part of `line` around `pos`
+fn snippet(line: &[u8], pos: usize) -> String {
+    let start = pos.saturating_sub(SNIPPET_CONTEXT);
+    let end = ::std::cmp::min(line.len(), pos + SNIPPET_CONTEXT);
+    if start >= end { return String::new(); }
+    String::from_utf8_lossy(&line[start..end]).into_owned()
 }
 
 /// Result type
 pub type Result<T> = ::std::result::Result<T, Error>;
 
+#[allow(deprecated)]
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -35,13 +138,26 @@ impl fmt::Display for Error {
             Error::Parse(ref err) => write!(f, "{}", err),
             Error::Io(ref err) => write!(f, "{}", err),
             Error::EOL => write!(f, "Trying to access column but found End Of Line"),
-            Error::UnescapedQuote => write!(f, "A CSV column has an unescaped quote"),
+            Error::UnescapedQuote { ref line, pos, line_number } => write!(f,
+                "A CSV column has an unescaped quote at line {}, byte {}: '{}'",
+                line_number, pos, snippet(line, pos)),
+            Error::UnexpectedQuote { ref line, pos, line_number } => write!(f,
+                "A CSV column has a quote but the entire column value is not quoted at line {}, byte {}: '{}'",
+                line_number, pos, snippet(line, pos)),
             Error::UnexpextedQuote => write!(f, "A CSV column has a quote but the entire column value is not quoted"),
-            Error::ColumnMismatch(exp, cur) => write!(f, "Expectiong {} columns, found {}", exp, cur),
+            Error::ColumnMismatch { expected, got, line } => write!(f,
+                "Expecting {} columns, found {} at line {}", expected, got, line),
+            Error::RecordTooLarge(max) => write!(f, "Record exceeds the maximum size of {} bytes", max),
+            Error::ParseField { column, ref value, ref kind } => write!(f,
+                "CSV decode error: failed converting column {} ('{}'): {}", column, value, kind),
+            Error::InvalidField { line, column, ref error } => write!(f,
+                "Invalid column {} at line {}: {}", column, line, error),
+            Error::InvalidConfig(ref msg) => write!(f, "Invalid CSV settings: {}", msg),
         }
     }
 }
 
+#[allow(deprecated)]
 impl ::std::error::Error for Error {
     fn description(&self) -> &str {
         match *self {
@@ -49,21 +165,110 @@ impl ::std::error::Error for Error {
             Error::Parse(..) => "CSV parse error",
             Error::Io(..) => "CSV IO error",
             Error::EOL => "Trying to access column but found End Of Line",
-            Error::UnescapedQuote => "A CSV column has an unescaped quote",
-            Error::UnexpextedQuote => "A CSV column has a quote but the entire column value is not quoted",
-            Error::ColumnMismatch(..) => "Current column count mismatch with previous rows",
+            Error::UnescapedQuote { .. } => "A CSV column has an unescaped quote",
+            Error::UnexpectedQuote { .. } | Error::UnexpextedQuote => "A CSV column has a quote but the entire column value is not quoted",
+            Error::ColumnMismatch { .. } => "Current column count mismatch with previous rows",
+            Error::RecordTooLarge(..) => "Record exceeds the maximum size",
+            Error::ParseField { .. } => "CSV column conversion error",
+            Error::InvalidField { .. } => "CSV column rejected by the validator",
+            Error::InvalidConfig(..) => "Invalid CSV settings",
         }
     }
 
     fn cause(&self) -> Option<&::std::error::Error> {
         match *self {
             Error::Io(ref err) => Some(err),
+            Error::InvalidField { ref error, .. } => Some(&**error),
             _ => None,
         }
     }
 }
 
+/// `Io` errors are cloned from their kind and message, the source error
+/// itself is not preserved
+#[allow(deprecated)]
+impl Clone for Error {
+    fn clone(&self) -> Error {
+        match *self {
+            Error::Decode(ref msg) => Error::Decode(msg.clone()),
+            Error::Parse(ref msg) => Error::Parse(msg.clone()),
+            Error::Io(ref err) => Error::Io(io::Error::new(err.kind(), err.to_string())),
+            Error::EOL => Error::EOL,
+            Error::UnescapedQuote { ref line, pos, line_number } => Error::UnescapedQuote {
+                line: line.clone(),
+                pos: pos,
+                line_number: line_number,
+            },
+            Error::UnexpectedQuote { ref line, pos, line_number } => Error::UnexpectedQuote {
+                line: line.clone(),
+                pos: pos,
+                line_number: line_number,
+            },
+            Error::UnexpextedQuote => Error::UnexpextedQuote,
+            Error::ColumnMismatch { expected, got, line } => {
+                Error::ColumnMismatch { expected: expected, got: got, line: line }
+            }
+            Error::RecordTooLarge(max) => Error::RecordTooLarge(max),
+            Error::ParseField { column, ref value, ref kind } => Error::ParseField {
+                column: column,
+                value: value.clone(),
+                kind: kind.clone(),
+            },
+            Error::InvalidField { line, column, ref error } => Error::InvalidField {
+                line: line,
+                column: column,
+                error: error.clone(),
+            },
+            Error::InvalidConfig(ref msg) => Error::InvalidConfig(msg.clone()),
+        }
+    }
+}
+
+/// `Io` errors are equal if they have the same `io::ErrorKind`, whatever
+/// their message
+#[allow(deprecated)]
+impl PartialEq for Error {
+    fn eq(&self, other: &Error) -> bool {
+        match (self, other) {
+            (Error::Decode(a), Error::Decode(b)) => a == b,
+            (Error::Parse(a), Error::Parse(b)) => a == b,
+            (Error::Io(a), Error::Io(b)) => a.kind() == b.kind(),
+            (Error::EOL, Error::EOL) => true,
+            (Error::UnescapedQuote { line: a, pos: i, line_number: m },
+             Error::UnescapedQuote { line: b, pos: j, line_number: n }) => {
+                a == b && i == j && m == n
+            }
+            (Error::UnexpectedQuote { line: a, pos: i, line_number: m },
+             Error::UnexpectedQuote { line: b, pos: j, line_number: n }) => {
+                a == b && i == j && m == n
+            }
+            (Error::UnexpextedQuote, Error::UnexpextedQuote) => true,
+            (Error::ColumnMismatch { expected: a, got: b, line: l },
+             Error::ColumnMismatch { expected: c, got: d, line: m }) => a == c && b == d && l == m,
+            (Error::RecordTooLarge(a), Error::RecordTooLarge(b)) => a == b,
+            (Error::ParseField { column: a, value: v, kind: k },
+             Error::ParseField { column: b, value: w, kind: l }) => {
+                a == b && v == w && k == l
+            }
+            (Error::InvalidField { line: a, column: b, error: e },
+             Error::InvalidField { line: c, column: d, error: f }) => {
+                a == c && b == d && e == f
+            }
+            (Error::InvalidConfig(a), Error::InvalidConfig(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
 impl From<::std::io::Error> for Error {
     fn from(err: ::std::io::Error) -> Error { Error::Io(err) }
 }
 
+impl From<Error> for io::Error {
+    fn from(err: Error) -> io::Error {
+        match err {
+            Error::Io(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Record index module
+//!
+//! Provides `RecordIndex`, the positions of the records of a seekable csv,
+//! and `IndexedCsv`, an exact size and double ended iterator built on it
+
+use std::io::{self, BufRead, Seek};
+
+use error::{Error, Result};
+use {Csv, Position, Row};
+
+/// Positions of the records of a seekable csv
+///
+/// The index only holds the starting position of each record, the records
+/// themselves being read again on demand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordIndex {
+    positions: Vec<Position>,
+}
+
+impl RecordIndex {
+
+    /// Builds the index by reading all the records of `csv`
+    ///
+    /// `csv` is rewound with `Csv::reset` before and after, the header row,
+    /// if any, is not indexed. The first malformed record stops the indexing
+    /// unless `OnError::Skip` is set, in which case it is not indexed.
+    pub fn build<B: BufRead + Seek>(csv: &mut Csv<B>) -> Result<RecordIndex> {
+        try!(csv.reset());
+        let mut positions = Vec::new();
+        let result = loop {
+            match csv.next() {
+                Some(Ok(_)) => positions.push(csv.record_start.clone()),
+                Some(Err(e)) => break Err(e),
+                None => break Ok(()),
+            }
+        };
+        try!(csv.reset());
+        result.map(|_| RecordIndex { positions: positions })
+    }
+
+    /// Gets the position of the `i`th record
+    pub fn get(&self, i: usize) -> Option<&Position> {
+        self.positions.get(i)
+    }
+
+    /// Gets the number of records
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Checks if there is no record
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+}
+
+/// Iterator on the records of a `Csv` along with their `RecordIndex`
+///
+/// The exact number of remaining records is known and records can also be
+/// read backwards.
+///
+/// The index must match the current contents of the input: records are read
+/// from the byte offsets stored in the index, a file modified since the index
+/// was built yields garbage records or errors.
+///
+/// # Example
+///
+/// ```rust
+/// use quick_csv::CsvBuilder;
+/// use quick_csv::index::{IndexedCsv, RecordIndex};
+/// use std::io::Cursor;
+///
+/// let mut csv = CsvBuilder::new().has_header(true).build(Cursor::new("h\n1\n2\n3\n"));
+/// let index = RecordIndex::build(&mut csv).unwrap();
+/// let rows = IndexedCsv::new(csv, index).rev()
+///     .map(|r| r.unwrap().decode::<u8>().unwrap())
+///     .collect::<Vec<_>>();
+/// assert_eq!(rows, vec![3, 2, 1]);
+/// ```
+pub struct IndexedCsv<B: BufRead + Seek> {
+    csv: Csv<B>,
+    index: RecordIndex,
+    front: usize,
+    back: usize,
+    /// index of the record the reader is positioned at, if known
+    next: Option<usize>,
+}
+
+impl<B: BufRead + Seek> IndexedCsv<B> {
+
+    /// Creates a new IndexedCsv, `csv` being the one `index` was built from
+    pub fn new(mut csv: Csv<B>, index: RecordIndex) -> IndexedCsv<B> {
+        csv.read_pending_header();
+        let back = index.len();
+        IndexedCsv {
+            csv: csv,
+            index: index,
+            front: 0,
+            back: back,
+            next: None,
+        }
+    }
+
+    /// Gets the underlying `Csv`, e.g. to get its headers
+    pub fn csv(&self) -> &Csv<B> {
+        &self.csv
+    }
+
+    /// Reads the `i`th record, seeking to it unless the reader is already
+    /// positioned at its start
+    fn read_at(&mut self, i: usize) -> Result<Row> {
+        if self.next != Some(i) {
+            self.next = None;
+            let position = self.index.positions[i].clone();
+            try!(self.csv.seek_to(&position));
+        }
+        match self.csv.read_row(false) {
+            Some(Ok(row)) => {
+                self.next = Some(i + 1);
+                Ok(row)
+            }
+            Some(Err(e)) => {
+                self.next = None;
+                Err(e)
+            }
+            None => {
+                self.next = None;
+                Err(Error::Io(io::Error::new(io::ErrorKind::UnexpectedEof,
+                                             "record missing, the index may be outdated")))
+            }
+        }
+    }
+
+}
+
+impl<B: BufRead + Seek> Iterator for IndexedCsv<B> {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Result<Row>> {
+        if self.front == self.back {
+            return None;
+        }
+        let i = self.front;
+        self.front += 1;
+        Some(self.read_at(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<B: BufRead + Seek> DoubleEndedIterator for IndexedCsv<B> {
+    fn next_back(&mut self) -> Option<Result<Row>> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        let i = self.back;
+        Some(self.read_at(i))
+    }
+}
+
+impl<B: BufRead + Seek> ExactSizeIterator for IndexedCsv<B> {
+    fn len(&self) -> usize {
+        self.back - self.front
+    }
+}
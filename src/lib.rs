@@ -21,6 +21,15 @@
 //! }
 //! ```
 //! 
+//! Settings, such as the delimiter or the header, are set on a `CsvBuilder`
+//!
+//! ```rust
+//! let csv = quick_csv::CsvBuilder::new()
+//!     .delimiter(b';')
+//!     .has_header(true)
+//!     .from_string("a;b\nc;d");
+//! ```
+//!
 //! `Row` is on the other hand provides 3 methods to access csv columns:
 //! - `columns`: 
 //!   - iterator over columns.
@@ -44,6 +53,16 @@
 //!   }
 //!   ``` 
 //! 
+//!   - `Csv::into_deserialize` decodes every row, skipping the header
+//!
+//!   ```rust
+//!   let csv = quick_csv::CsvBuilder::new().has_header(true).from_string("a,b\nx,1\ny,2");
+//!   for row in csv.into_deserialize::<(String, u64)>() {
+//!       let (col1, col2) = row.unwrap();
+//!       println!("col1: '{}', col2: {}", col1, col2);
+//!   }
+//!   ```
+//!
 //! - `bytes_columns`:
 //!   - similar to `columns` but columns are of type `&[u8]`, which means you may want to convert it to &str first
 //!   - performance gain compared to `columns` is minimal, use it only if you *really* need to as it is less convenient
@@ -51,23 +70,193 @@
 #![deny(missing_docs)]
 
 extern crate rustc_serialize;
+#[cfg(feature = "flate2")]
+extern crate flate2;
+#[cfg(feature = "memchr")]
+extern crate memchr;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+#[cfg(feature = "chrono")]
+extern crate chrono;
 
+pub mod builder;
 pub mod columns;
+#[cfg(feature = "encoding")]
+pub mod encoding;
 pub mod error;
+pub mod index;
+pub mod multi;
+pub mod schema;
+pub mod writer;
+
+pub use builder::CsvBuilder;
 
-use self::columns::{Columns, BytesColumns};
+use self::columns::{Columns, BytesColumns, DecodeSettings, QuotedColumns};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
-use std::iter::Iterator;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, StdinLock};
+use std::iter::{Enumerate, Iterator};
+use std::marker::PhantomData;
+use std::ops::Range;
 use std::path::Path;
+use std::slice::Iter;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::thread::{self, JoinHandle};
 
 use error::{Error, Result};
+use schema::ColumnType;
 use rustc_serialize::Decodable;
 
 #[cfg(test)] mod test;
 
 const UTF8_BOM: &'static [u8] = b"\xef\xbb\xbf";
 
+/// Transformation applied to every column, see `CsvBuilder::map_fields`
+type FieldMap = Box<dyn Fn(&str) -> Cow<str> + Send + Sync>;
+
+/// Validation of every column, with its index, see `CsvBuilder::validate_field`
+type FieldValidator = Box<dyn Fn(usize, &str) -> Result<()> + Send + Sync>;
+
+/// Progress callback, see `CsvBuilder::on_progress`
+type ProgressCallback = Box<dyn FnMut(u64) + Send>;
+
+/// Position of a `Csv` reader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    /// Bytes consumed from the underlying reader
+    pub byte: u64,
+    /// Rows read, including skipped ones (see `Csv::current_line`)
+    pub line: usize,
+    /// Rows successfully read
+    pub record: usize,
+}
+
+/// Line terminator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Terminator {
+    /// `\n`, optionally preceded by `\r`
+    Crlf,
+    /// `\r` only, `\n` being an ordinary byte
+    Cr,
+    /// Any of `\n`, `\r\n` or `\r`
+    Any,
+    /// A single byte, e.g. `\x1e`
+    Byte(u8),
+    /// A short byte sequence, an empty sequence never matches
+    Bytes(Vec<u8>),
+    /// Detected from the first line end of the input: `Crlf` if it is
+    /// `\r\n`, `Cr` if it is a lone `\r`, `Byte(b'\n')` if it is a lone `\n`
+    ///
+    /// Only the first buffer of the reader is looked at, so the result
+    /// depends on its size: `Any` is used if it has no line end, or ends
+    /// with a `\r`, and by `parse_record`. The terminator is detected again
+    /// after `reset`.
+    Auto,
+}
+
+impl Terminator {
+    /// Checks if `b` ends a line, or starts the line ending sequence
+    fn is_end(&self, b: u8) -> bool {
+        match *self {
+            Terminator::Crlf => b == b'\n',
+            Terminator::Cr => b == b'\r',
+            Terminator::Any | Terminator::Auto => b == b'\n' || b == b'\r',
+            Terminator::Byte(t) => b == t,
+            Terminator::Bytes(ref seq) => seq.first() == Some(&b),
+        }
+    }
+
+    /// Gets the byte ending a line, or starting the line ending sequence
+    ///
+    /// `\r` is also a line end in `Any` mode
+    #[cfg(feature = "memchr")]
+    fn end_byte(&self) -> u8 {
+        match *self {
+            Terminator::Crlf | Terminator::Any | Terminator::Auto => b'\n',
+            Terminator::Cr => b'\r',
+            Terminator::Byte(t) => t,
+            Terminator::Bytes(ref seq) => seq.first().cloned().unwrap_or(b'\n'),
+        }
+    }
+
+    /// Checks if `b` is any of the terminator bytes
+    fn contains(&self, b: u8) -> bool {
+        match *self {
+            Terminator::Crlf | Terminator::Any | Terminator::Auto => b == b'\n' || b == b'\r',
+            Terminator::Cr => b == b'\r',
+            Terminator::Byte(t) => b == t,
+            Terminator::Bytes(ref seq) => seq.contains(&b),
+        }
+    }
+
+    /// Checks if `rest`, the bytes following a closing quote, may start the
+    /// line terminator, a `Bytes` sequence possibly ending in the next buffer
+    fn follows_quote(&self, rest: &[u8]) -> bool {
+        match *self {
+            Terminator::Bytes(ref seq) => {
+                !seq.is_empty() && (rest.starts_with(seq) || seq.starts_with(rest))
+            }
+            _ => rest.first().map_or(false, |&b| {
+                self.is_end(b) || (self.strips_cr() && b == b'\r')
+            }),
+        }
+    }
+
+    /// Checks if a trailing `\r` is part of the terminator
+    fn strips_cr(&self) -> bool {
+        matches!(*self, Terminator::Crlf | Terminator::Any | Terminator::Auto)
+    }
+}
+
+/// Behavior of the `Csv` iterator when a row is malformed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnError {
+    /// Yields the error and stops iterating
+    Stop,
+    /// Records the error, skips the row and continues with the next one
+    ///
+    /// A malformed header row is not skipped, its error is returned by
+    /// `try_headers`
+    Skip,
+}
+
+/// Handling of rows with more columns than the first row, or the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtraColumns {
+    /// Yields an `Error::ColumnMismatch`
+    Error,
+    /// Drops the extra columns
+    Truncate,
+    /// Appends the extra columns, delimiters included, to the last column
+    Merge,
+}
+
+/// Handling of `\r\n` line breaks within quoted fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewlineInQuotes {
+    /// Keeps the line breaks as they appear in the input
+    Preserve,
+    /// Replaces `\r\n` by `\n`, a lone `\r` being kept
+    NormalizeLf,
+}
+
+/// Handling of rows which are not valid UTF-8
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Utf8Mode {
+    /// Rows are read as they are, `Row::columns` and `Row::decode` failing
+    Strict,
+    /// Invalid sequences are replaced with `U+FFFD REPLACEMENT CHARACTER`
+    Lossy,
+    /// Rows are skipped, they are still counted by `Csv::current_line`
+    SkipRow,
+}
+
 /// Csv reader
 /// 
 /// Iterates over the rows of the csv
@@ -94,6 +283,16 @@ const UTF8_BOM: &'static [u8] = b"\xef\xbb\xbf";
 ///
 /// }
 /// ```
+///
+/// # Thread safety
+///
+/// `Csv<B>` is `Send` (resp. `Sync`) whenever `B` is, e.g.
+/// `Csv<BufReader<File>>` can be moved to another thread while
+/// `Csv<StdinLock>` cannot. Closures given to `CsvBuilder::map_fields` and
+/// `CsvBuilder::validate_field` must be `Send + Sync` for that reason. The
+/// `CsvBuilder::on_progress` callback, only called through `&mut Csv`, is
+/// only required to be `Send`: it is kept behind a `Mutex`, never locked.
+/// `Row`s and the decoding settings they share are always `Send + Sync`.
 pub struct Csv<B: BufRead> {
     /// delimiter
     delimiter: u8,
@@ -101,16 +300,89 @@ pub struct Csv<B: BufRead> {
     reader: B,
     /// header
     has_header: bool,
+    /// header row not read yet, see `read_pending_header`
+    header_pending: bool,
     /// header
     headers: Option<Vec<String>>,
+    /// headers given by `set_headers`, kept by `reset`
+    headers_given: bool,
+    /// header positions by name, built on first `header_index` call
+    header_map: OnceLock<HashMap<String, usize>>,
+    /// error while reading the header, returned by next `try_headers` call
+    headers_error: Option<Error>,
     /// flexible column count
     flexible: bool,
+    /// discard empty lines
+    skip_empty_lines: bool,
+    /// consecutive delimiters considered as one
+    collapse_delimiters: bool,
+    /// drop an unquoted empty last column
+    trim_trailing_empty: bool,
+    /// handling of rows with too many columns
+    extra_columns: ExtraColumns,
+    /// quotes handling
+    quoting: bool,
+    /// line terminator
+    terminator: Terminator,
+    /// handling of `\r\n` within quoted fields
+    newline_in_quotes: NewlineInQuotes,
+    /// handling of invalid UTF-8
+    utf8: Utf8Mode,
+    /// columns returned as they appear in the input
+    raw_fields: bool,
+    /// decoding settings
+    settings: Arc<DecodeSettings>,
+    /// selected columns indices
+    selection: Option<Vec<usize>>,
+    /// transformation applied to every column
+    field_map: Option<FieldMap>,
+    /// validation of every column, with its index
+    field_validator: Option<FieldValidator>,
+    /// row read ahead by `peek_record`
+    peeked: Option<Option<Result<Row>>>,
     /// column count
     len: Option<usize>,
+    /// initial line buffer capacity
+    buf_capacity: usize,
+    /// initial column count capacity, until the column count is known
+    cols_capacity: usize,
+    /// maximum record size in bytes
+    max_record_size: usize,
     /// if was error, exit next
     exit: bool,
     /// line count
     current_line: usize,
+    /// successfully read records count
+    record: usize,
+    /// position of the start of the last record read
+    record_start: Position,
+    /// buffers of the record lent by `next_borrowed`
+    borrowed: ByteRecord,
+    /// total input size in bytes, if known
+    total_bytes: Option<u64>,
+    /// bytes consumed so far
+    bytes_read: u64,
+    /// strip a leading UTF-8 BOM
+    skip_bom: bool,
+    /// size of the preamble discarded before the first record
+    skip_bytes: u64,
+    /// data rows skipped after the header
+    skip_rows: usize,
+    /// `skip_rows` rows not skipped yet, see `read_pending_header`
+    skip_rows_pending: bool,
+    /// whether the leading BOM has already been looked for
+    bom_checked: bool,
+    /// `terminator` was detected from `Terminator::Auto`, restored by `reset`
+    terminator_detected: bool,
+    /// behavior on malformed rows
+    on_error: OnError,
+    /// skipped rows errors
+    errors: Vec<(usize, Error)>,
+    /// progress callback, called every given number of bytes, the `Mutex`
+    /// only making `Csv` `Sync`
+    progress: Option<(u64, Mutex<ProgressCallback>)>,
+    /// bytes read triggering the next progress callback
+    next_progress: u64,
 }
 
 impl<B: BufRead> Csv<B> {
@@ -118,55 +390,515 @@ impl<B: BufRead> Csv<B> {
     /// Creates a Csv from a generic BufReader
     /// 
     /// Note: default delimiter = ','
-    pub fn from_reader(mut reader: B) -> Csv<B> {
-        let result = try_consume_utf8_bom(&mut reader);
-
+    pub fn from_reader(reader: B) -> Csv<B> {
         Csv {
             reader: reader,
             delimiter: b',',
             has_header: false,
+            header_pending: false,
             headers: None,
+            headers_given: false,
+            header_map: OnceLock::new(),
+            headers_error: None,
             flexible: false,
+            skip_empty_lines: false,
+            collapse_delimiters: false,
+            trim_trailing_empty: false,
+            extra_columns: ExtraColumns::Error,
+            quoting: true,
+            terminator: Terminator::Crlf,
+            newline_in_quotes: NewlineInQuotes::Preserve,
+            utf8: Utf8Mode::Strict,
+            raw_fields: false,
+            settings: Arc::new(DecodeSettings::default()),
+            selection: None,
+            field_map: None,
+            field_validator: None,
+            peeked: None,
             len: None,
-            exit: result.is_err(),
+            buf_capacity: 0,
+            cols_capacity: 0,
+            max_record_size: usize::MAX,
+            exit: false,
             current_line: 0,
+            record: 0,
+            record_start: Position { byte: 0, line: 0, record: 0 },
+            borrowed: ByteRecord::new(),
+            total_bytes: None,
+            bytes_read: 0,
+            skip_bom: true,
+            skip_bytes: 0,
+            skip_rows: 0,
+            skip_rows_pending: false,
+            bom_checked: false,
+            terminator_detected: false,
+            on_error: OnError::Stop,
+            errors: Vec::new(),
+            progress: None,
+            next_progress: 0,
         }
     }
 
+    /// Creates a Csv from a generic BufReader, with a given delimiter
+    ///
+    /// Shortcut for `CsvBuilder::new().delimiter(delimiter).build(reader)`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delimiter` is the quote or a line terminator byte
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let csv = quick_csv::Csv::from_reader_with_delimiter("a;b\nc;d".as_bytes(), b';');
+    /// assert_eq!(csv.map(|r| r.unwrap().len()).collect::<Vec<_>>(), vec![2, 2]);
+    /// ```
+    pub fn from_reader_with_delimiter(reader: B, delimiter: u8) -> Csv<B> {
+        CsvBuilder::new().delimiter(delimiter).build(reader)
+    }
+
     /// Sets a new delimiter
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings become inconsistent, see `CsvBuilder::validate`
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::delimiter` instead")]
     pub fn delimiter(mut self, delimiter: u8) -> Csv<B> {
         self.delimiter = delimiter;
+        self.validated()
+    }
+
+    /// Sets the delimiter to a tab and disables quoting, for tab-separated files
+    ///
+    /// Quoting can be enabled again by calling `quoting(true)` afterwards
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings become inconsistent, see `CsvBuilder::validate`
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::tsv` instead")]
+    pub fn tsv(mut self) -> Csv<B> {
+        self.delimiter = b'\t';
+        self.quoting = false;
+        self.validated()
+    }
+
+    /// Defines whether quotes are handled (default: true)
+    ///
+    /// When disabled, quotes are ordinary bytes: columns are split on
+    /// delimiters and line terminators only and are never unquoted
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings become inconsistent, see `CsvBuilder::validate`
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::quoting` instead")]
+    pub fn quoting(mut self, quoting: bool) -> Csv<B> {
+        self.quoting = quoting;
+        self.validated()
+    }
+
+    /// Sets the line terminator (default: `Terminator::Crlf`)
+    ///
+    /// Terminators are ignored within quoted fields
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings become inconsistent, see `CsvBuilder::validate`
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::terminator` instead")]
+    pub fn terminator(mut self, terminator: Terminator) -> Csv<B> {
+        self.terminator = terminator;
+        self.validated()
+    }
+
+    /// Defines whether columns are returned untouched (default: false)
+    ///
+    /// When enabled, surrounding quotes and doubled quotes escapes are kept
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::raw_fields` instead")]
+    pub fn raw_fields(mut self, raw_fields: bool) -> Csv<B> {
+        self.raw_fields = raw_fields;
+        self
+    }
+
+    /// Sets additional tokens decoded as booleans, e.g. `yes`/`no`
+    ///
+    /// `true` and `false` are always accepted
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::bool_tokens` instead")]
+    pub fn bool_tokens(mut self, true_tokens: &[&str], false_tokens: &[&str]) -> Csv<B> {
+        {
+            let settings = Arc::make_mut(&mut self.settings);
+            settings.true_tokens = true_tokens.iter().map(|t| t.to_string()).collect();
+            settings.false_tokens = false_tokens.iter().map(|t| t.to_string()).collect();
+        }
+        self
+    }
+
+    /// Sets additional tokens decoded as infinity, negative infinity and NaN
+    /// floats, e.g. `Inf`, `-Infinity` or `NA`
+    ///
+    /// Tokens are compared case insensitively. Rust's own `inf`, `-inf`,
+    /// `infinity` and `NaN` are always accepted. Missing values such as
+    /// `#N/A` are better handled with `null_tokens`
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::float_tokens` instead")]
+    pub fn float_tokens(mut self, infinity: &[&str], neg_infinity: &[&str], nan: &[&str])
+        -> Csv<B>
+    {
+        {
+            let settings = Arc::make_mut(&mut self.settings);
+            settings.infinity_tokens = infinity.iter().map(|t| t.to_string()).collect();
+            settings.neg_infinity_tokens = neg_infinity.iter().map(|t| t.to_string()).collect();
+            settings.nan_tokens = nan.iter().map(|t| t.to_string()).collect();
+        }
+        self
+    }
+
+    /// Sets additional tokens decoded as `None`, e.g. `NA` or `\N`
+    ///
+    /// Empty columns are always decoded as `None`
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::null_tokens` instead")]
+    pub fn null_tokens(mut self, null_tokens: Vec<String>) -> Csv<B> {
+        Arc::make_mut(&mut self.settings).null_tokens = null_tokens;
+        self
+    }
+
+    /// Defines whether null tokens are compared case insensitively (default: false)
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::null_ignore_case` instead")]
+    pub fn null_ignore_case(mut self, ignore_case: bool) -> Csv<B> {
+        Arc::make_mut(&mut self.settings).null_ignore_case = ignore_case;
+        self
+    }
+
+    /// Sets the decimal separator of floats (default: `b'.'`)
+    ///
+    /// E.g. `b','` to decode `3,14` as `3.14`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings become inconsistent, see `CsvBuilder::validate`
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::decimal_separator` instead")]
+    pub fn decimal_separator(mut self, separator: u8) -> Csv<B> {
+        Arc::make_mut(&mut self.settings).decimal_separator = match separator {
+            b'.' => None,
+            s => Some(s),
+        };
+        self.validated()
+    }
+
+    /// Sets a thousands separator, removed before decoding numbers (default: none)
+    ///
+    /// E.g. `b'.'`, along with `decimal_separator(b',')`, to decode
+    /// `1.234,56` as `1234.56`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings become inconsistent, see `CsvBuilder::validate`
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::thousands_separator` instead")]
+    pub fn thousands_separator(mut self, separator: u8) -> Csv<B> {
+        Arc::make_mut(&mut self.settings).thousands_separator = Some(separator);
+        self.validated()
+    }
+
+    /// Applies `f` to every column of the data rows, quotes being already
+    /// stripped
+    ///
+    /// The header row is left unchanged, as are the columns which are not
+    /// valid UTF-8. This is opt-in
+    /// as every row is then copied into a new buffer, whether `f` modifies
+    /// it or not.
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::map_fields` instead")]
+    pub fn map_fields<F>(mut self, f: F) -> Csv<B>
+        where F: Fn(&str) -> Cow<str> + Send + Sync + 'static
+    {
+        self.field_map = Some(Box::new(f));
+        self
+    }
+
+    /// Validates every column with `f`, called with the column index and
+    /// content
+    ///
+    /// Validation runs on all the columns of the data rows, before `select`
+    /// and `map_fields`, even on columns which are not decoded later. The
+    /// header row is not validated.
+    /// Errors are yielded as `Error::InvalidField`, along with the line
+    /// number, and handled as malformed rows as per `on_error`.
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::validate_field` instead")]
+    pub fn validate_field<F>(mut self, f: F) -> Csv<B>
+        where F: Fn(usize, &str) -> Result<()> + Send + Sync + 'static
+    {
+        self.field_validator = Some(Box::new(f));
+        self
+    }
+
+    /// Sets the radix of integers (default: 10, using `FromStr`)
+    ///
+    /// Once set, integers may also be prefixed with `0x`, `0o` or `0b`,
+    /// which take precedence over `radix`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not in the range from 2 to 36, see
+    /// `CsvBuilder::validate`
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::integer_radix` instead")]
+    pub fn integer_radix(mut self, radix: u32) -> Csv<B> {
+        Arc::make_mut(&mut self.settings).integer_radix = Some(radix);
+        self.validated()
+    }
+
+    /// Selects a subset of columns, in the given order
+    ///
+    /// Rows only expose the selected columns. A row missing any of the
+    /// selected columns is a malformed row, yielded as an
+    /// `Error::ColumnMismatch` and handled as per `on_error`.
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::select` instead")]
+    pub fn select(mut self, indices: Vec<usize>) -> Csv<B> {
+        self.selection = Some(indices);
         self
     }
 
     /// Sets flexible columns
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::flexible` instead")]
     pub fn flexible(mut self, flexible: bool) -> Csv<B> {
         self.flexible = flexible;
         self
     }
 
+    /// Sets the initial capacity of each row, in bytes and in columns
+    ///
+    /// Avoids reallocations on the first rows of wide files. Once the
+    /// column count is known, it is used as columns capacity instead
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::record_capacity` instead")]
+    pub fn record_capacity(mut self, bytes: usize, fields: usize) -> Csv<B> {
+        self.buf_capacity = bytes;
+        self.cols_capacity = fields;
+        self
+    }
+
+    /// Sets the maximum size of a record, in bytes (default: unlimited)
+    ///
+    /// Guards against unterminated quotes on untrusted inputs, which would
+    /// otherwise buffer the rest of the input as a single record. Larger
+    /// records are reported as `Error::RecordTooLarge` and the iteration
+    /// stops. The limit is checked each time the reader buffer is exhausted,
+    /// so up to one reader buffer more may be allocated.
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::max_record_size` instead")]
+    pub fn max_record_size(mut self, max_record_size: usize) -> Csv<B> {
+        self.max_record_size = max_record_size;
+        self
+    }
+
+    /// Defines what to do when a row is malformed (default: `OnError::Stop`)
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::on_error` instead")]
+    pub fn on_error(mut self, on_error: OnError) -> Csv<B> {
+        self.on_error = on_error;
+        self
+    }
+
+    /// Defines whether a leading UTF-8 BOM is discarded (default: true)
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::skip_bom` instead")]
+    pub fn skip_bom(mut self, skip_bom: bool) -> Csv<B> {
+        self.skip_bom = skip_bom;
+        self
+    }
+
+    /// Defines whether empty lines are discarded (default: false)
+    ///
+    /// When disabled, empty lines are rows with a single empty column
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::skip_empty_lines` instead")]
+    pub fn skip_empty_lines(mut self, skip_empty_lines: bool) -> Csv<B> {
+        self.skip_empty_lines = skip_empty_lines;
+        self
+    }
+
+    /// Splits columns on runs of spaces (default: false)
+    ///
+    /// Leading and trailing delimiters are ignored and consecutive ones
+    /// are considered as a single delimiter. Use `delimiter` afterwards to
+    /// collapse another byte, e.g. `b'\t'`.
+    ///
+    /// Quoting still applies: a quoted field, even `""`, is always a column.
+    /// The column count checked when not `flexible` is the collapsed one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings become inconsistent, see `CsvBuilder::validate`
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::whitespace_delimited` instead")]
+    pub fn whitespace_delimited(mut self, whitespace_delimited: bool) -> Csv<B> {
+        if whitespace_delimited {
+            self.delimiter = b' ';
+        }
+        self.collapse_delimiters = whitespace_delimited;
+        self.validated()
+    }
+
+    /// Defines whether missing trailing columns are decoded as empty columns
+    /// (default: false)
+    ///
+    /// Useful with `flexible(true)`: when decoding a tuple or a struct, short
+    /// rows are completed with empty columns, decoded as `""` or `None`
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::fill_missing` instead")]
+    pub fn fill_missing(mut self, fill_missing: bool) -> Csv<B> {
+        Arc::make_mut(&mut self.settings).fill_missing = fill_missing;
+        self
+    }
+
     /// Defines whether there is a header or not
+    ///
+    /// The header row is read by the first call to `next`, `headers` or
+    /// any other reading method, not by `has_header` itself
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::has_header` instead")]
     pub fn has_header(mut self, has_header: bool) -> Csv<B> {
         self.has_header = has_header;
-        let _ = self.headers();
+        self.header_pending = has_header;
+        self
+    }
+
+    /// Skips the `n` first data rows
+    ///
+    /// Unlike `Iterator::skip`, the header row is never counted. Rows are
+    /// skipped by the first reading method, as per `Iterator::nth`
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::skip_rows` instead")]
+    pub fn skip_rows(mut self, n: usize) -> Csv<B> {
+        self.skip_rows = n;
+        self.skip_rows_pending = n > 0;
+        self
+    }
+
+    /// Sets the headers without reading any row
+    ///
+    /// Useful when headers are known out of band: `has_header` is left
+    /// unchanged, if set the first row is still consumed but these headers
+    /// are kept
+    #[deprecated(since = "0.2.0", note = "use `CsvBuilder::set_headers` instead")]
+    pub fn set_headers(mut self, headers: Vec<String>) -> Csv<B> {
+        self.headers = Some(headers);
+        self.headers_given = true;
+        self.header_map = OnceLock::new();
+        self.headers_error = None;
         self
     }
 
    /// gets first row as Vec<String>
+   ///
+   /// Reads the header row if not read yet. Returns an empty `Vec` if the
+   /// header cannot be read, see `try_headers`
     pub fn headers(&mut self) -> Vec<String> {
-        if let Some(ref h) = self.headers {
-            return h.clone();
-        }
-        if self.has_header {            
-            if let Some(r) = self.next() {
-                if let Ok(r) = r {
-                    let h = r.decode().ok().unwrap_or_else(Vec::new);
-                    self.headers = Some(h.clone());
-                    return h;
+        self.try_headers().unwrap_or_else(|_| Vec::new())
+    }
+
+    /// Gets first row as Vec<String>, propagating any error
+    ///
+    /// The header row is only read once: if it fails, the error is returned
+    /// once and later calls return an empty `Vec`
+    pub fn try_headers(&mut self) -> Result<Vec<String>> {
+        self.read_pending_header();
+        if let Some(e) = self.headers_error.take() {
+            return Err(e);
+        }
+        Ok(self.headers.clone().unwrap_or_default())
+    }
+
+    /// Reads the header row if `has_header` is set and it is not read yet,
+    /// then skips the `skip_rows` first data rows
+    ///
+    /// Headers given by `set_headers` are kept. Errors are returned by the
+    /// next `try_headers` call, or by the next `next` call for skipped rows.
+    fn read_pending_header(&mut self) {
+        if self.header_pending {
+            self.header_pending = false;
+            self.read_header();
+        }
+        if self.skip_rows_pending {
+            self.skip_rows_pending = false;
+            if let Some(Err(e)) = self.nth(self.skip_rows - 1) {
+                self.peeked = Some(Some(Err(e)));
+            }
+        }
+    }
+
+    /// Reads the header row, see `read_pending_header`
+    fn read_header(&mut self) {
+        let headers: Result<Vec<String>> = match self.read_row(true) {
+            Some(Ok(r)) => r.decode(),
+            Some(Err(e)) => Err(e),
+            None => return,
+        };
+        match headers {
+            Ok(h) => if self.headers.is_none() {
+                self.headers = Some(h);
+                self.header_map = OnceLock::new();
+            },
+            Err(e) => {
+                if self.headers.is_none() {
+                    self.headers = Some(Vec::new());
                 }
+                self.headers_error = Some(e);
             }
         }
-        Vec::new()
+    }
+
+    /// Gets the headers, without cloning them
+    ///
+    /// Returns `None` until the header row is read, by the first call to
+    /// `next` or `headers`, or until headers are set by `set_headers`
+    pub fn header_names(&self) -> Option<&[String]> {
+        self.headers.as_ref().map(|h| &h[..])
+    }
+
+    /// Gets the index of the column named `name`
+    ///
+    /// The first call builds a map of the headers, reused afterwards.
+    /// If several columns have the same name, the first one is returned
+    pub fn header_index(&self, name: &str) -> Option<usize> {
+        let headers = match self.headers {
+            Some(ref h) => h,
+            None => return None,
+        };
+        let map = self.header_map.get_or_init(|| {
+            let mut map = HashMap::with_capacity(headers.len());
+            for (i, h) in headers.iter().enumerate() {
+                map.entry(h.clone()).or_insert(i);
+            }
+            map
+        });
+        map.get(name).cloned()
+    }
+
+    /// Gets the delimiter
+    pub fn get_delimiter(&self) -> u8 {
+        self.delimiter
+    }
+
+    /// Checks if rows may have different column counts
+    pub fn is_flexible(&self) -> bool {
+        self.flexible
+    }
+
+    /// Checks if the first row is considered as a header
+    pub fn has_header_flag(&self) -> bool {
+        self.has_header
+    }
+
+    /// Checks if quotes are handled
+    pub fn is_quoting(&self) -> bool {
+        self.quoting
+    }
+
+    /// Gets the line terminator
+    pub fn get_terminator(&self) -> &Terminator {
+        &self.terminator
+    }
+
+    /// Checks if columns are returned as they appear in the input
+    pub fn is_raw_fields(&self) -> bool {
+        self.raw_fields
+    }
+
+    /// Checks if empty lines are discarded
+    pub fn is_skip_empty_lines(&self) -> bool {
+        self.skip_empty_lines
+    }
+
+    /// Gets what is done when a row is malformed
+    pub fn get_on_error(&self) -> OnError {
+        self.on_error
     }
 
     /// Get column count
@@ -181,126 +913,1376 @@ impl<B: BufRead> Csv<B> {
         self.current_line
     }
 
-}
+    /// Gets the number of bytes consumed from the underlying reader
+    ///
+    /// Tells an empty input, which yields no row, from a blank one: `""`
+    /// yields no row while `"\n"` yields a row with a single empty column,
+    /// unless `skip_empty_lines` is set, but 1 byte is read. A leading BOM
+    /// is counted as well.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
 
-impl Csv<BufReader<File>> {
-    /// Creates a csv from a file path
-    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Csv<BufReader<File>>>
-    {
-        let reader = BufReader::new(try!(File::open(path)));
-        Ok(Csv::from_reader(reader))
+    /// Gets the current position of the reader
+    ///
+    /// The byte offset includes delimiters, terminators and BOM so it can be
+    /// used to `Seek` to the start of the next row
+    pub fn position(&self) -> Position {
+        Position {
+            byte: self.bytes_read,
+            line: self.current_line,
+            record: self.record,
+        }
     }
-}
 
-impl<'a> Csv<&'a [u8]> {
-    /// Creates a CSV reader for an in memory string buffer.
-    pub fn from_string(s: &'a str) -> Csv<&'a [u8]> {
-        Csv::from_reader(s.as_bytes())
+    /// Gets the errors of the rows skipped so far, with their line number
+    ///
+    /// Only populated with `OnError::Skip`
+    pub fn errors(&self) -> &[(usize, Error)] {
+        &self.errors
     }
-}
 
-/// Iterator on csv `Row`s
-impl<B: BufRead> Iterator for Csv<B> {
-    type Item = Result<Row>;
-    fn next(&mut self) -> Option<Result<Row>> {
-        if self.exit { return None; }
-        let mut buf = Vec::new();
-        let mut cols = self.len.map_or_else(Vec::new, Vec::with_capacity);
-        match read_line(&mut self.reader, &mut buf, self.delimiter, &mut cols) {
-            Ok(0) => None,
-            Ok(_n) => {
-                if buf.ends_with(&[b'\r']) {
-                    buf.pop();
-                }
-                cols.push(buf.len());
-                let c = cols.len();
-                if let Some(n) = self.len {
-                    if n != c && !self.flexible {
-                        self.exit = true;
-                        return Some(Err(Error::ColumnMismatch(n, c)));
-                    }
-                } else {
-                    self.len = Some(c);
-                }
-                self.current_line += 1;
-                Some(Ok(Row {
-                    line: buf,
-                    cols: cols,
-                }))
-            }
-            Err(e) => {
-                self.exit = true;
-                Some(Err(e))
-            },
+    /// Estimates the number of rows left, based on the remaining bytes
+    /// and the average line length seen so far
+    ///
+    /// Returns `None` if the input size is unknown or no row has been read yet
+    pub fn estimated_remaining(&self) -> Option<usize> {
+        if self.current_line == 0 { return None; }
+        self.remaining_bytes().map(|r| {
+            let avg = self.bytes_read / self.current_line as u64;
+            (r / ::std::cmp::max(avg, 1)) as usize
+        })
+    }
+
+    /// Clears the error latch, to resume reading after an error
+    ///
+    /// Unless `OnError::Skip` is set, reading methods stop at the first
+    /// error. Once its cause is gone, e.g. a transient io error, reading
+    /// goes on from the current position of the reader, which may be in the
+    /// middle of a record: on seekable readers, `seek_to` a known `position`
+    /// instead, which clears the latch as well.
+    pub fn clear_error(&mut self) {
+        self.exit = false;
+        if let Some(None) = self.peeked {
+            self.peeked = None;
         }
     }
-}
 
-/// Row struct used as Csv iterator Item
-///
-/// Row can be decoded into a Result<T: Decodable>
-pub struct Row {
-    line: Vec<u8>,
-    cols: Vec<usize>,
-}
+    fn remaining_bytes(&self) -> Option<u64> {
+        self.total_bytes.map(|t| t.saturating_sub(self.bytes_read))
+    }
 
-impl Row {
+    /// Reads the next row ahead without consuming it
+    ///
+    /// The next call to `next` returns this row instead of reading a new
+    /// one. Counters such as `position` already include the peeked row
+    pub fn peek_record(&mut self) -> Option<::std::result::Result<&Row, &Error>> {
+        self.read_pending_header();
+        if self.peeked.is_none() {
+            self.peeked = Some(self.read_row(false));
+        }
+        match self.peeked {
+            Some(Some(ref r)) => Some(r.as_ref()),
+            _ => None,
+        }
+    }
 
-    /// Gets an iterator over columns
-    pub fn columns(&self) -> Result<Columns> {
-        match ::std::str::from_utf8(&self.line) {
-            Err(_) => Err(Error::Io(io::Error::new(io::ErrorKind::InvalidData,
-                                    "stream did not contain valid UTF-8"))),
-            Ok(s) => Ok(Columns::new(s, &self.cols)),
+    /// Counts the remaining records without parsing columns
+    ///
+    /// Line terminators within quotes are ignored but quotes are not
+    /// validated, hence malformed rows are counted as well, only a quote
+    /// left open at the end of the input is an `Error::Parse`. The reader is
+    /// consumed: the iterator is exhausted afterwards, use `reset` on
+    /// seekable readers to read the rows.
+    pub fn count_records(&mut self) -> Result<usize> {
+        self.read_pending_header();
+        let mut count = match self.peeked.take() {
+            Some(Some(_)) => 1,
+            Some(None) => return Ok(0),
+            None => 0,
+        };
+        if self.exit {
+            return Ok(count);
         }
+        try!(self.check_bom());
+        self.exit = true;
+        let (records, read) = try!(count_lines(&mut self.reader, self.quoting, &self.terminator,
+                                               self.skip_empty_lines, usize::MAX,
+                                               self.current_line));
+        self.bytes_read += read as u64;
+        self.report_progress();
+        count += records;
+        Ok(count)
     }
 
-    ///  Creates a new BytesColumns iterator over &[u8]
-    pub fn bytes_columns(&self) -> BytesColumns {
-        BytesColumns::new(&self.line, &self.cols)
+    /// Checks all the remaining rows against `schema`, e.g. one returned by
+    /// `infer_schema`
+    ///
+    /// Stops at the first value not accepted by its `ColumnType`, returned as
+    /// an `Error::Parse` with its line and column index. Columns missing
+    /// from a row are null values, columns beyond the schema are errors.
+    pub fn validate_schema(&mut self, schema: &[ColumnType]) -> Result<()> {
+        while let Some(row) = self.next() {
+            let row = try!(row);
+            let columns = try!(row.columns());
+            let len = columns.len();
+            let line = self.current_line;
+            for (i, col) in columns.enumerate() {
+                let error = match schema.get(i) {
+                    Some(t) if t.accepts(col, &self.settings) => continue,
+                    Some(t) => format!("expected {:?}{}, found '{}'", t.field_type,
+                                       if t.nullable { " or null" } else { "" }, col),
+                    None => format!("expected {} columns, found '{}'", schema.len(), col),
+                };
+                return Err(Error::Parse(format!("Invalid column {} at line {}: {}",
+                                                i, line, error)));
+            }
+            if let Some(i) = schema.iter().skip(len).position(|t| !t.nullable) {
+                return Err(Error::Parse(format!(
+                    "Invalid column {} at line {}: expected {:?}, found a missing column",
+                    len + i, line, schema[len + i].field_type)));
+            }
+        }
+        Ok(())
     }
 
-    /// Decode row into custom decodable type
-    pub fn decode<T: Decodable>(&self) -> Result<T> {
-        let mut columns = try!(self.columns());
-        Decodable::decode(&mut columns)
+    /// Reads the next record into `record`, reusing its buffers
+    ///
+    /// Returns `Ok(false)` at the end of the input. Unlike iterating rows,
+    /// no allocation is needed once `record` is large enough.
+    pub fn read_byte_record(&mut self, record: &mut ByteRecord) -> Result<bool> {
+        self.read_pending_header();
+        match self.peeked.take() {
+            Some(Some(Ok(row))) => {
+                record.line = row.line.into_bytes();
+                record.ends = row.cols;
+                record.unquote = row.unquote;
+                return Ok(true);
+            }
+            Some(Some(Err(e))) => return Err(e),
+            Some(None) => return Ok(false),
+            None => (),
+        }
+        record.unquote = self.unquote(false);
+        match self.read_record(&mut record.line, &mut record.ends, false) {
+            Some(Ok(())) => Ok(true),
+            Some(Err(e)) => Err(e),
+            None => Ok(false),
+        }
     }
 
-    /// Gets columns count
+    /// Reads the next record into buffers owned by the `Csv` and lends it
+    ///
+    /// The fastest way to go through the columns: buffers are reused and no
+    /// `Row` or `ByteRecord` is built. The record borrows the `Csv`, hence
+    /// only one can be alive at a time, which also means `next_borrowed`
+    /// cannot be used with a `for` loop.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut csv = quick_csv::Csv::from_string("a,b\nc,d\n");
+    /// let mut firsts = Vec::new();
+    /// while let Some(record) = csv.next_borrowed() {
+    ///     firsts.push(record.unwrap().get(0).unwrap().to_vec());
+    /// }
+    /// assert_eq!(firsts, vec![b"a".to_vec(), b"c".to_vec()]);
+    /// ```
+    pub fn next_borrowed(&mut self) -> Option<Result<BorrowedRecord<'_>>> {
+        let mut record = ::std::mem::replace(&mut self.borrowed, ByteRecord::new());
+        let result = self.read_byte_record(&mut record);
+        self.borrowed = record;
+        match result {
+            Ok(true) => Some(Ok(BorrowedRecord {
+                line: &self.borrowed.line,
+                ends: &self.borrowed.ends,
+                unquote: self.borrowed.unquote,
+            })),
+            Ok(false) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Writes the remaining rows as newline-delimited JSON objects
+    ///
+    /// Keys are the headers if `has_header` is set, the column indices
+    /// (`"0"`, `"1"` ...) otherwise or for columns beyond the headers.
+    /// Values are always JSON strings.
+    ///
+    /// Requires the `serde_json` feature
+    #[cfg(feature = "serde_json")]
+    pub fn to_json_lines<W: io::Write>(&mut self, mut out: W) -> Result<()> {
+        fn write_str<W: io::Write>(out: &mut W, s: &str) -> Result<()> {
+            serde_json::to_writer(out, s).map_err(|e| Error::Io(e.into()))
+        }
+        let headers = try!(self.try_headers());
+        for row in self {
+            let row = try!(row);
+            try!(out.write_all(b"{"));
+            for (i, col) in try!(row.columns()).enumerate() {
+                if i > 0 {
+                    try!(out.write_all(b","));
+                }
+                match headers.get(i) {
+                    Some(h) => try!(write_str(&mut out, h)),
+                    None => try!(write_str(&mut out, &i.to_string())),
+                }
+                try!(out.write_all(b":"));
+                try!(write_str(&mut out, col));
+            }
+            try!(out.write_all(b"}\n"));
+        }
+        Ok(())
+    }
+
+    /// Converts into an iterator of owned `StringRecord`s
+    ///
+    /// Convenient when rows need to outlive the iteration
+    pub fn records(self) -> Records<B> {
+        Records { csv: self }
+    }
+
+    /// Converts into an iterator of rows along with their starting `Position`
+    ///
+    /// The position is the one of the first byte of the row itself, after
+    /// any skipped empty line, and its counters are the ones before the row
+    /// is read. Malformed rows come with their own position as well.
+    pub fn records_with_position(self) -> RecordsWithPosition<B> {
+        RecordsWithPosition { csv: self }
+    }
+
+    /// Converts into an iterator of owned columns
+    ///
+    /// Each row is yielded as a `Vec<String>`, or as an error if it cannot be
+    /// read or is not valid UTF-8
+    pub fn into_owned_rows(self) -> OwnedRows<B> {
+        OwnedRows { csv: self }
+    }
+
+    /// Converts into an iterator of rows decoded into `T`
+    ///
+    /// Same as `Row::decode` on every row, the header row, if any, being
+    /// skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use quick_csv::CsvBuilder;
+    ///
+    /// let csv = CsvBuilder::new()
+    ///     .has_header(true)
+    ///     .select(vec![0, 12])
+    ///     .from_file("./examples/data/bench.csv")
+    ///     .unwrap();
+    /// for game in csv.into_deserialize::<(String, u64)>() {
+    ///     let (id, season) = game.unwrap();
+    ///     assert!(id.starts_with("2012"));
+    ///     assert_eq!(season, 2012);
+    /// }
+    /// ```
+    pub fn into_deserialize<T: Decodable>(self) -> DeserializeIterator<B, T> {
+        DeserializeIterator {
+            csv: self,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Reads all rows sequentially then decodes them on rayon's thread pool
+    ///
+    /// The whole file is held in memory. The returned iterator is indexed:
+    /// `collect` preserves the file order while `for_each` does not.
+    ///
+    /// Requires the `rayon` feature
+    #[cfg(feature = "rayon")]
+    pub fn par_decode<T>(self) -> ParDecode<T>
+        where T: Decodable + Send
+    {
+        use rayon::prelude::*;
+        fn decode<T: Decodable>(row: Result<Row>) -> Result<T> {
+            row.and_then(|r| r.decode())
+        }
+        self.collect::<Vec<_>>().into_par_iter().map(decode::<T>)
+    }
+
+}
+
+impl<B: BufRead + Seek> Csv<B> {
+
+    /// Rewinds the reader to the start of the input
+    ///
+    /// Settings are kept while the reading state (line number, column count,
+    /// skipped rows errors ...) is rolled back. If `has_header` is set, the
+    /// header row is read again by the next reading method, headers given by
+    /// `set_headers` being kept.
+    pub fn reset(&mut self) -> Result<()> {
+        try!(self.reader.seek(SeekFrom::Start(0)));
+        self.exit = false;
+        self.current_line = 0;
+        self.record = 0;
+        self.bytes_read = 0;
+        self.next_progress = self.progress.as_ref().map_or(0, |p| p.0);
+        self.len = None;
+        self.bom_checked = false;
+        if self.terminator_detected {
+            self.terminator_detected = false;
+            self.terminator = Terminator::Auto;
+        }
+        self.peeked = None;
+        self.errors.clear();
+        if self.has_header {
+            if !self.headers_given {
+                self.headers = None;
+                self.header_map = OnceLock::new();
+            }
+            self.headers_error = None;
+            self.header_pending = true;
+        }
+        self.skip_rows_pending = self.skip_rows > 0;
+        Ok(())
+    }
+
+    /// Moves the reader to `position`, the start of a record
+    ///
+    /// `position` is typically saved with `position` beforehand, e.g. to
+    /// retry the following records after an error: the error latch is
+    /// cleared. The header row, if any, is read first if it has not been yet.
+    pub fn seek_to(&mut self, position: &Position) -> Result<()> {
+        // positions already account for skipped rows
+        self.skip_rows_pending = false;
+        self.read_pending_header();
+        try!(self.reader.seek(SeekFrom::Start(position.byte)));
+        self.exit = false;
+        self.bytes_read = position.byte;
+        self.current_line = position.line;
+        self.record = position.record;
+        self.bom_checked = true;
+        self.header_pending = false;
+        self.peeked = None;
+        Ok(())
+    }
+
+    /// Guesses the type of each column from the `sample` first rows
+    ///
+    /// Values are decoded as per the decoding settings (null tokens,
+    /// separators ...). Columns missing from some rows are nullable.
+    /// The reader is rewound with `reset` afterwards so no row is lost.
+    pub fn infer_schema(&mut self, sample: usize) -> Result<Vec<ColumnType>> {
+        let mut schema: Vec<ColumnType> = Vec::new();
+        let mut rows = 0;
+        let mut result = Ok(());
+        while rows < sample {
+            let row = match self.next() {
+                Some(Ok(row)) => row,
+                Some(Err(e)) => {
+                    result = Err(e);
+                    break;
+                }
+                None => break,
+            };
+            let columns = match row.columns() {
+                Ok(columns) => columns,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            };
+            let len = columns.len();
+            for (i, col) in columns.enumerate() {
+                if i == schema.len() {
+                    // columns missing from previous rows
+                    let nullable = rows > 0;
+                    schema.push(ColumnType { nullable: nullable, ..ColumnType::new() });
+                }
+                schema[i].update(col, &self.settings);
+            }
+            for c in schema.iter_mut().skip(len) {
+                c.nullable = true;
+            }
+            rows += 1;
+        }
+        try!(self.reset());
+        result.map(|_| schema)
+    }
+
+}
+
+impl<B: BufRead + Send + 'static> Csv<B> {
+
+    /// Reads records on a background thread and sends them over a channel
+    ///
+    /// At most `buffer` records are pending at any time. The thread ends on
+    /// end of file, after sending the error which stopped the reader, or as
+    /// soon as the `Receiver` is dropped.
+    pub fn into_channel(self, buffer: usize)
+        -> (Receiver<Result<StringRecord>>, JoinHandle<()>)
+    {
+        let (tx, rx) = sync_channel(buffer);
+        let handle = thread::spawn(move || {
+            for record in self.records() {
+                if tx.send(record).is_err() {
+                    break;
+                }
+            }
+        });
+        (rx, handle)
+    }
+
+}
+
+impl Csv<BufReader<File>> {
+    /// Creates a csv from a file path
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Csv<BufReader<File>>>
+    {
+        let file = try!(File::open(path));
+        let total_bytes = file.metadata().ok().and_then(|m| file_len(&m));
+        let mut csv = Csv::from_reader(BufReader::new(file));
+        csv.total_bytes = total_bytes;
+        Ok(csv)
+    }
+
+    /// Creates a csv from a file path, with a given delimiter
+    ///
+    /// Shortcut for `CsvBuilder::new().delimiter(delimiter).from_file(path)`,
+    /// an invalid delimiter being an `Error::InvalidConfig`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let csv = quick_csv::Csv::from_file_with_delimiter("./examples/data/bench.csv", b',')
+    ///     .unwrap();
+    /// assert_eq!(csv.get_delimiter(), b',');
+    /// ```
+    pub fn from_file_with_delimiter<P: AsRef<Path>>(path: P, delimiter: u8)
+        -> Result<Csv<BufReader<File>>>
+    {
+        CsvBuilder::new().delimiter(delimiter).from_file(path)
+    }
+
+    /// Creates a csv from a file path, with a delimiter guessed from its
+    /// extension
+    ///
+    /// See `CsvBuilder::extension_delimiter` for the mapping, use it along
+    /// with `CsvBuilder::from_file` to change other settings.
+    pub fn from_path_auto<P: AsRef<Path>>(path: P) -> Result<Csv<BufReader<File>>>
+    {
+        CsvBuilder::new().extension_delimiter(&path).from_file(path)
+    }
+
+    /// Creates a tab-separated csv from a file path
+    pub fn from_tsv_file<P: AsRef<Path>>(path: P) -> Result<Csv<BufReader<File>>>
+    {
+        CsvBuilder::new().tsv().from_file(path)
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl Csv<BufReader<flate2::read::GzDecoder<File>>> {
+    /// Creates a csv from a gzip compressed file path
+    ///
+    /// Requires the `flate2` feature
+    pub fn from_gzip_file<P: AsRef<Path>>(path: P)
+        -> Result<Csv<BufReader<flate2::read::GzDecoder<File>>>>
+    {
+        let file = try!(File::open(path));
+        Ok(Csv::from_reader(BufReader::new(flate2::read::GzDecoder::new(file))))
+    }
+}
+
+impl Csv<multi::MultiFileReader> {
+    /// Creates a csv reading several files one after the other, as a single
+    /// csv
+    ///
+    /// Files are concatenated as they are, see `CsvBuilder::from_files` to
+    /// skip the header repeated in every file.
+    ///
+    /// Lines, records and bytes are counted over the whole concatenation,
+    /// e.g. `current_line` keeps growing from one file to the next. Use
+    /// `MultiFileReader::current_path` to get the file being read.
+    pub fn from_files<P: AsRef<Path>>(paths: &[P]) -> Result<Csv<multi::MultiFileReader>> {
+        let reader = multi::MultiFileReader::new(paths, false);
+        let total_bytes = try!(reader.max_len());
+        let mut csv = Csv::from_reader(reader);
+        csv.total_bytes = total_bytes;
+        Ok(csv)
+    }
+}
+
+impl Csv<StdinLock<'static>> {
+    /// Creates a csv reading from the locked standard input
+    ///
+    /// Stdin is already buffered, no `BufReader` is needed
+    pub fn from_stdin() -> Csv<StdinLock<'static>> {
+        Csv::from_reader(io::stdin().lock())
+    }
+}
+
+impl<'a> Csv<&'a [u8]> {
+    /// Creates a CSV reader for an in memory string buffer.
+    pub fn from_string(s: &'a str) -> Csv<&'a [u8]> {
+        let mut csv = Csv::from_reader(s.as_bytes());
+        csv.total_bytes = Some(s.len() as u64);
+        csv
+    }
+
+    /// Creates a CSV reader for an in memory string buffer, with a given
+    /// delimiter
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delimiter` is the quote or a line terminator byte
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut csv = quick_csv::Csv::from_string_with_delimiter("a|b|c", b'|');
+    /// assert_eq!(csv.next().unwrap().unwrap().len(), 3);
+    /// ```
+    pub fn from_string_with_delimiter(s: &'a str, delimiter: u8) -> Csv<&'a [u8]> {
+        CsvBuilder::new().delimiter(delimiter).from_string(s)
+    }
+}
+
+impl<B: BufRead> Csv<B> {
+    /// Reads next row from the underlying reader, the header row if `header`
+    fn read_row(&mut self, header: bool) -> Option<Result<Row>> {
+        let mut buf = Vec::with_capacity(self.buf_capacity);
+        let mut cols = Vec::with_capacity(self.len.unwrap_or(self.cols_capacity));
+        self.read_record(&mut buf, &mut cols, header).map(|r| r.map(|()| Row {
+            line: RowLine::new(buf),
+            cols: cols,
+            unquote: self.unquote(header),
+            line_number: self.current_line,
+            settings: self.settings.clone(),
+        }))
+    }
+
+    /// Runs the field validator, if any, on the columns of the next line
+    fn invalid_field(&self, buf: &[u8], cols: &[usize]) -> Option<Error> {
+        let f = match self.field_validator {
+            Some(ref f) => f,
+            None => return None,
+        };
+        let columns = BytesColumns::new(buf, cols).unquote(self.quoting && !self.raw_fields);
+        for (i, col) in columns.enumerate() {
+            let result = match ::std::str::from_utf8(col) {
+                Ok(col) => f(i, col),
+                Err(e) => Err(Error::Io(io::Error::new(io::ErrorKind::InvalidData, e))),
+            };
+            if let Err(e) = result {
+                return Some(Error::InvalidField {
+                    line: self.current_line + 1,
+                    column: i,
+                    error: Box::new(e),
+                });
+            }
+        }
+        None
+    }
+
+    /// Checks the settings changed by the deprecated setters
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings are inconsistent, as `CsvBuilder::build`
+    fn validated(self) -> Csv<B> {
+        if let Err(e) = validate_settings(self.delimiter, self.quoting, &self.terminator,
+                                       &self.settings) {
+            panic!("{}", e);
+        }
+        self
+    }
+
+    /// Keeps the selected columns only, if any, of the next line
+    ///
+    /// Returns an `Error::ColumnMismatch` if a selected column is missing
+    fn select_fields(&self, buf: &mut Vec<u8>, cols: &mut Vec<usize>) -> Option<Error> {
+        let indices = match self.selection {
+            Some(ref indices) => indices,
+            None => return None,
+        };
+        match select_columns(buf, cols, indices, self.delimiter) {
+            Some((b, c)) => {
+                *buf = b;
+                *cols = c;
+                None
+            }
+            None => Some(Error::ColumnMismatch {
+                expected: indices.iter().max().map_or(0, |&i| i + 1),
+                got: cols.len(),
+                line: self.current_line + 1,
+            }),
+        }
+    }
+
+    /// Calls the progress callback if the next threshold is reached
+    fn report_progress(&mut self) {
+        if let Some((every, ref mut f)) = self.progress {
+            if self.bytes_read >= self.next_progress {
+                let f = f.get_mut().unwrap_or_else(|e| e.into_inner());
+                f(self.bytes_read);
+                self.next_progress = (self.bytes_read / every + 1) * every;
+            }
+        }
+    }
+
+    /// Consumes the preamble and the leading BOM on first call, if
+    /// `skip_bom`, and detects the `Terminator::Auto` terminator
+    fn check_bom(&mut self) -> Result<()> {
+        if !self.bom_checked {
+            self.bom_checked = true;
+            if self.skip_bytes > 0 {
+                let mut preamble = io::Read::take(&mut self.reader, self.skip_bytes);
+                self.bytes_read += try!(io::copy(&mut preamble, &mut io::sink()));
+            }
+            if self.skip_bom {
+                self.bytes_read += try!(try_consume_utf8_bom(&mut self.reader)) as u64;
+            }
+            if self.terminator == Terminator::Auto {
+                self.terminator = try!(detect_terminator(&mut self.reader));
+                self.terminator_detected = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks if rows columns are still quoted, the header row being never
+    /// mapped
+    fn unquote(&self, header: bool) -> bool {
+        self.quoting && !self.raw_fields && (header || self.field_map.is_none())
+    }
+
+    /// Reads the next record into `buf`, `cols` being filled with the
+    /// columns ends
+    ///
+    /// The header row is not validated by the field validator nor mapped by
+    /// the field map, nor skipped with `OnError::Skip` if malformed
+    fn read_record(&mut self, buf: &mut Vec<u8>, cols: &mut Vec<usize>, header: bool)
+        -> Option<Result<()>>
+    {
+        if self.exit { return None; }
+        if let Err(e) = self.check_bom() {
+            self.exit = true;
+            return Some(Err(e));
+        }
+        loop {
+            buf.clear();
+            cols.clear();
+            self.record_start = Csv::position(self);
+            let mut read = 0;
+            let result = read_line(&mut self.reader, buf, self.delimiter, self.quoting,
+                                   !self.raw_fields, &self.terminator, self.newline_in_quotes,
+                                   self.max_record_size, cols, &mut read);
+            self.bytes_read += read as u64;
+            self.report_progress();
+            let err = match result {
+                Ok(()) if read == 0 => return None,
+                Ok(()) => {
+                    if self.terminator.strips_cr() && buf.ends_with(&[b'\r']) {
+                        buf.pop();
+                    }
+                    if self.collapse_delimiters {
+                        let (b, c) = collapse_delimiters(buf, cols, self.delimiter);
+                        *buf = b;
+                        *cols = c;
+                    }
+                    if self.skip_empty_lines && buf.is_empty() && cols.is_empty() {
+                        continue;
+                    }
+                    cols.push(buf.len());
+                    if self.utf8 != Utf8Mode::Strict && ::std::str::from_utf8(buf).is_err() {
+                        if self.utf8 == Utf8Mode::SkipRow {
+                            self.current_line += 1;
+                            continue;
+                        }
+                        let (b, c) = lossy_fields(buf, cols, self.delimiter);
+                        *buf = b;
+                        *cols = c;
+                    }
+                    if self.trim_trailing_empty {
+                        trim_trailing_empty(buf, cols);
+                    }
+                    match self.len {
+                        Some(n) if n < cols.len() && !self.flexible => match self.extra_columns {
+                            ExtraColumns::Error => (),
+                            ExtraColumns::Truncate => {
+                                cols.truncate(n);
+                                buf.truncate(cols[n - 1]);
+                            }
+                            ExtraColumns::Merge => {
+                                merge_columns(buf, cols, n, self.delimiter,
+                                              self.quoting && !self.raw_fields);
+                            }
+                        },
+                        _ => (),
+                    }
+                    let c = cols.len();
+                    match self.len {
+                        Some(n) if n != c && !self.flexible => Error::ColumnMismatch {
+                            expected: n,
+                            got: c,
+                            line: self.current_line + 1,
+                        },
+                        _ => {
+                            if self.len.is_none() {
+                                self.len = Some(c);
+                            }
+                            let invalid = if header { None } else { self.invalid_field(buf, cols) };
+                            if let Some(e) = invalid {
+                                e
+                            } else if let Some(e) = self.select_fields(buf, cols) {
+                                e
+                            } else {
+                                self.current_line += 1;
+                                match self.field_map {
+                                    Some(ref f) if !header => {
+                                        let (b, c) = map_fields(buf, cols,
+                                                                self.quoting && !self.raw_fields,
+                                                                self.delimiter, &**f);
+                                        *buf = b;
+                                        *cols = c;
+                                    }
+                                    _ => (),
+                                }
+                                self.record += 1;
+                                return Some(Ok(()));
+                            }
+                        }
+                    }
+                }
+                Err(e @ Error::UnescapedQuote { .. }) | Err(e @ Error::UnexpectedQuote { .. }) => {
+                    if self.on_error == OnError::Skip {
+                        // resynchronize on the next line terminator
+                        match skip_line(&mut self.reader, &self.terminator) {
+                            Ok(n) => self.bytes_read += n as u64,
+                            Err(e) => {
+                                self.exit = true;
+                                return Some(Err(e));
+                            }
+                        }
+                    }
+                    // `read_line` doesn't know the line number
+                    let line_number = self.current_line + 1;
+                    match e {
+                        Error::UnescapedQuote { line, pos, .. } => Error::UnescapedQuote {
+                            line: line,
+                            pos: pos,
+                            line_number: line_number,
+                        },
+                        Error::UnexpectedQuote { line, pos, .. } => Error::UnexpectedQuote {
+                            line: line,
+                            pos: pos,
+                            line_number: line_number,
+                        },
+                        e => e,
+                    }
+                }
+                Err(Error::Parse(msg)) => {
+                    self.exit = true;
+                    let msg = format!("line {}: {}", self.current_line + 1, msg);
+                    return Some(Err(Error::Parse(msg)));
+                },
+                Err(e) => {
+                    self.exit = true;
+                    return Some(Err(e));
+                },
+            };
+            match self.on_error {
+                OnError::Stop => {
+                    self.exit = true;
+                    return Some(Err(err));
+                }
+                OnError::Skip if header => {
+                    // the next row must not be taken for the header
+                    self.current_line += 1;
+                    return Some(Err(err));
+                }
+                OnError::Skip => {
+                    self.current_line += 1;
+                    self.errors.push((self.current_line, err));
+                }
+            }
+        }
+    }
+}
+
+/// Iterator on csv `Row`s
+impl<B: BufRead> Iterator for Csv<B> {
+    type Item = Result<Row>;
+    fn next(&mut self) -> Option<Result<Row>> {
+        self.read_pending_header();
+        match self.peeked.take() {
+            Some(row) => row,
+            None => self.read_row(false),
+        }
+    }
+
+    /// Skips `n` rows by only looking for line terminators, without parsing
+    /// their columns
+    ///
+    /// Skipped rows are hence neither validated nor checked for column
+    /// count mismatches, a quote left open at the end of the input being the
+    /// only error reported. With `OnError::Skip` or `Utf8Mode::SkipRow`,
+    /// malformed rows must not be counted and the rows are fully parsed.
+    fn nth(&mut self, mut n: usize) -> Option<Result<Row>> {
+        self.read_pending_header();
+        if self.on_error == OnError::Skip || self.utf8 == Utf8Mode::SkipRow
+            || self.collapse_delimiters {
+            for _ in 0..n {
+                if self.next().is_none() {
+                    return None;
+                }
+            }
+            return self.next();
+        }
+        match self.peeked.take() {
+            Some(Some(row)) => {
+                if n == 0 {
+                    return Some(row);
+                }
+                n -= 1;
+            }
+            Some(None) => return None,
+            None => (),
+        }
+        if n > 0 && !self.exit {
+            let skipped = self.check_bom().and_then(|_| {
+                count_lines(&mut self.reader, self.quoting, &self.terminator,
+                            self.skip_empty_lines, n, self.current_line)
+            });
+            match skipped {
+                Ok((count, read)) => {
+                    self.bytes_read += read as u64;
+                    self.report_progress();
+                    self.current_line += count;
+                    self.record += count;
+                }
+                Err(e) => {
+                    self.exit = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let peeked = match self.peeked {
+            Some(Some(_)) => 1,
+            Some(None) => return (0, Some(0)),
+            None => 0,
+        };
+        if self.exit { return (peeked, Some(peeked)); }
+        // every row consumes at least one byte so the remaining bytes are a
+        // safe upper bound, while they may all be skipped (BOM, empty lines,
+        // invalid rows ...): only the peeked row is certain.
+        // See `estimated_remaining` for an estimate
+        let upper = self.remaining_bytes()
+            .and_then(|r| usize::try_from(r).ok())
+            .and_then(|r| r.checked_add(peeked));
+        (peeked, upper)
+    }
+}
+
+/// Row struct used as Csv iterator Item
+///
+/// Row can be decoded into a Result<T: Decodable>
+#[derive(Clone)]
+pub struct Row {
+    line: RowLine,
+    cols: Vec<usize>,
+    /// strip surrounding quotes of columns
+    unquote: bool,
+    /// line number, as per `Csv::current_line`
+    line_number: usize,
+    /// decoding settings
+    settings: Arc<DecodeSettings>,
+}
+
+/// Line of a `Row`, validated as UTF-8 once when the row is read
+#[derive(Clone)]
+enum RowLine {
+    /// valid UTF-8 line
+    Str(String),
+    /// invalid UTF-8 line, with the length of its valid prefix
+    Bytes(Vec<u8>, usize),
+}
+
+impl RowLine {
+    fn new(line: Vec<u8>) -> RowLine {
+        match String::from_utf8(line) {
+            Ok(s) => RowLine::Str(s),
+            Err(e) => {
+                let valid_up_to = e.utf8_error().valid_up_to();
+                RowLine::Bytes(e.into_bytes(), valid_up_to)
+            }
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match *self {
+            RowLine::Str(ref s) => s.as_bytes(),
+            RowLine::Bytes(ref b, _) => b,
+        }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        match self {
+            RowLine::Str(s) => s.into_bytes(),
+            RowLine::Bytes(b, _) => b,
+        }
+    }
+}
+
+/// Shows the columns, as `&str` or as bytes if not valid utf8
+impl fmt::Debug for Row {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = f.debug_struct("Row");
+        s.field("line", &self.line_number);
+        match self.columns() {
+            Ok(cols) => s.field("columns", &cols.collect::<Vec<_>>()),
+            Err(_) => s.field("columns", &self.bytes_columns().collect::<Vec<_>>()),
+        };
+        s.finish()
+    }
+}
+
+/// Rows are compared column by column, after quotes stripping, so `"a",b`
+/// equals `a,b`. The raw bytes and line numbers are not compared
+impl PartialEq for Row {
+    fn eq(&self, other: &Row) -> bool {
+        self.bytes_columns().eq(other.bytes_columns())
+    }
+}
+
+impl Row {
+
+    /// Gets an iterator over columns
+    pub fn columns(&self) -> Result<Columns<'_>> {
+        let s = try!(self.as_str());
+        Ok(Columns::new(s, &self.cols)
+           .unquote(self.unquote)
+           .settings(&self.settings))
+    }
+
+    /// Creates a new QuotedColumns iterator over (&str, bool), the flag being
+    /// set if the column was surrounded by quotes in the input
+    ///
+    /// Columns are never flagged if quotes are not stripped, e.g. with
+    /// `Csv::raw_fields`
+    pub fn columns_with_quoting(&self) -> Result<QuotedColumns<'_>> {
+        let s = try!(self.as_str());
+        Ok(QuotedColumns::new(s, &self.cols).unquote(self.unquote))
+    }
+
+    /// Gets the whole parsed line, without its terminator
+    ///
+    /// This is not a copy of the input: doubled quotes are unescaped unless
+    /// `raw_fields` is set, and settings such as `select`, `map_fields` or
+    /// `NewlineInQuotes::NormalizeLf` apply as well. Offsets within it
+    /// hence differ from the input ones after such a change.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.line.as_bytes()
+    }
+
+    /// Gets the whole parsed line as a `&str`, see `as_bytes`
+    ///
+    /// The line is validated once, when the row is read, hence calls to
+    /// `as_str`, `columns` or `decode` are cheap.
+    pub fn as_str(&self) -> Result<&str> {
+        match self.line {
+            RowLine::Str(ref s) => Ok(s),
+            RowLine::Bytes(_, valid_up_to) => {
+                Err(Error::Io(io::Error::new(io::ErrorKind::InvalidData,
+                    format!("stream did not contain valid UTF-8 at byte {} of line {}",
+                            valid_up_to, self.line_number))))
+            }
+        }
+    }
+
+    /// Gets the byte range of each column within `as_bytes`
+    ///
+    /// Ranges include the surrounding quotes, e.g. `a,"b,c",d` has columns
+    /// `0..1`, `2..7` and `8..9`, and exclude the delimiters. They are
+    /// positions within the parsed line, not the input: they drift from the
+    /// input ones after a doubled quote, unless `raw_fields` is set.
+    pub fn column_ranges(&self) -> Vec<Range<usize>> {
+        let mut start = 0;
+        self.cols.iter().map(|&end| {
+            let range = start..end;
+            start = end + 1;
+            range
+        }).collect()
+    }
+
+    ///  Creates a new BytesColumns iterator over &[u8]
+    pub fn bytes_columns(&self) -> BytesColumns<'_> {
+        BytesColumns::new(self.line.as_bytes(), &self.cols).unquote(self.unquote)
+    }
+
+    /// Iterates over the columns decoded from a single-byte encoding, such
+    /// as Windows-1252, instead of UTF-8
+    ///
+    /// Columns are borrowed when they are ASCII only.
+    ///
+    /// Requires the `encoding` feature
+    #[cfg(feature = "encoding")]
+    pub fn columns_encoded(&self, encoding: encoding::Encoding) -> encoding::EncodedColumns<'_> {
+        encoding::EncodedColumns::new(self.bytes_columns(), encoding)
+    }
+
+    /// Iterates over the columns as `&[u8]`, same as `bytes_columns`
+    pub fn iter(&self) -> BytesColumns<'_> {
+        self.bytes_columns()
+    }
+
+    /// Decode row into custom decodable type
+    ///
+    /// A `Vec` takes all the remaining columns, hence a homogeneous row can
+    /// be decoded as a whole, e.g. `1,2,3` into a `Vec<f64>`, or after a few
+    /// leading columns, e.g. `abc,1,2` into a `(String, Vec<u8>)`. A fixed
+    /// size array `[T; N]` does too and fails unless exactly `N` are left.
+    ///
+    /// ```rust
+    /// let row = quick_csv::Csv::from_string("1,2,3,4").next().unwrap().unwrap();
+    /// assert_eq!(row.decode::<Vec<f64>>().unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+    /// ```
+    pub fn decode<T: Decodable>(&self) -> Result<T> {
+        let mut columns = try!(self.columns());
+        Decodable::decode(&mut columns)
+    }
+
+    /// Decode row into custom decodable type, replacing invalid UTF-8
+    /// sequences with `U+FFFD REPLACEMENT CHARACTER`
+    pub fn decode_lossy<T: Decodable>(&self) -> Result<T> {
+        if self.as_str().is_ok() {
+            return self.decode();
+        }
+        // columns may grow once converted, rebuild the line and its offsets
+        let mut line = String::with_capacity(self.line.as_bytes().len() + 8);
+        let mut cols = Vec::with_capacity(self.cols.len());
+        for col in BytesColumns::new(self.line.as_bytes(), &self.cols).unquote(false) {
+            if !cols.is_empty() {
+                line.push(',');
+            }
+            line.push_str(&String::from_utf8_lossy(col));
+            cols.push(line.len());
+        }
+        let mut columns = Columns::new(&line, &cols)
+            .unquote(self.unquote)
+            .settings(&self.settings);
+        Decodable::decode(&mut columns)
+    }
+
+    /// Gets columns count
+    pub fn len(&self) -> usize {
+        self.cols.len()
+    }
+
+    /// `Row` is empty if there is no columns
+    pub fn is_empty(&self) -> bool {
+        self.cols.is_empty()
+    }
+
+}
+
+impl<'a> TryFrom<&'a Row> for Vec<String> {
+    type Error = Error;
+    fn try_from(row: &'a Row) -> Result<Vec<String>> {
+        Ok(try!(row.columns()).map(|c| c.to_string()).collect())
+    }
+}
+
+impl<'a> From<&'a Row> for Vec<Vec<u8>> {
+    fn from(row: &'a Row) -> Vec<Vec<u8>> {
+        row.bytes_columns().map(|c| c.to_vec()).collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a Row {
+    type Item = &'a [u8];
+    type IntoIter = BytesColumns<'a>;
+    fn into_iter(self) -> BytesColumns<'a> {
+        self.bytes_columns()
+    }
+}
+
+/// Owned csv record
+///
+/// Holds the parsed, quote-stripped columns of a `Row`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringRecord {
+    fields: Vec<String>,
+}
+
+impl StringRecord {
+
+    /// Creates a new `StringRecord` from a `Row`
+    pub fn from_row(row: &Row) -> Result<StringRecord> {
+        let columns = try!(row.columns());
+        Ok(StringRecord {
+            fields: columns.map(|c| c.to_string()).collect(),
+        })
+    }
+
+    /// Gets the column at index `i`, if any
+    pub fn get(&self, i: usize) -> Option<&str> {
+        self.fields.get(i).map(|f| &**f)
+    }
+
+    /// Gets columns count
     pub fn len(&self) -> usize {
-        self.cols.len()
+        self.fields.len()
     }
 
-    /// `Row` is empty if there is no columns
+    /// `StringRecord` is empty if there is no columns
     pub fn is_empty(&self) -> bool {
-        self.cols.is_empty()
+        self.fields.is_empty()
+    }
+
+    /// Consumes the record and returns its columns
+    pub fn into_vec(self) -> Vec<String> {
+        self.fields
+    }
+
+}
+
+/// Parallel iterator on decoded rows, created by `Csv::par_decode`
+#[cfg(feature = "rayon")]
+pub type ParDecode<T> = rayon::iter::Map<rayon::vec::IntoIter<Result<Row>>,
+                                         fn(Result<Row>) -> Result<T>>;
+
+/// Reusable csv record, filled by `Csv::read_byte_record`
+///
+/// Columns are never validated as UTF-8
+#[derive(Debug, Clone)]
+pub struct ByteRecord {
+    line: Vec<u8>,
+    ends: Vec<usize>,
+    unquote: bool,
+}
+
+impl ByteRecord {
+
+    /// Creates a new empty `ByteRecord`
+    pub fn new() -> ByteRecord {
+        ByteRecord::with_capacity(0, 0)
+    }
+
+    /// Creates a new empty `ByteRecord` with preallocated buffers
+    pub fn with_capacity(bytes: usize, fields: usize) -> ByteRecord {
+        ByteRecord {
+            line: Vec::with_capacity(bytes),
+            ends: Vec::with_capacity(fields),
+            unquote: true,
+        }
+    }
+
+    /// Gets the column at index `i`, if any
+    pub fn get(&self, i: usize) -> Option<&[u8]> {
+        record_field(&self.line, &self.ends, self.unquote, i)
+    }
+
+    /// Iterates over the columns
+    pub fn iter(&self) -> BytesColumns<'_> {
+        BytesColumns::new(&self.line, &self.ends).unquote(self.unquote)
+    }
+
+    /// Gets columns count
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// `ByteRecord` is empty if there is no columns
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+}
+
+impl Default for ByteRecord {
+    fn default() -> ByteRecord {
+        ByteRecord::new()
+    }
+}
+
+impl<'a> IntoIterator for &'a ByteRecord {
+    type Item = &'a [u8];
+    type IntoIter = BytesColumns<'a>;
+    fn into_iter(self) -> BytesColumns<'a> {
+        self.iter()
+    }
+}
+
+/// Csv record borrowing the buffers of a `Csv`, lent by `Csv::next_borrowed`
+///
+/// Columns are never validated as UTF-8
+#[derive(Debug, Clone, Copy)]
+pub struct BorrowedRecord<'a> {
+    line: &'a [u8],
+    ends: &'a [usize],
+    unquote: bool,
+}
+
+impl<'a> BorrowedRecord<'a> {
+
+    /// Gets the column at index `i`, if any
+    pub fn get(&self, i: usize) -> Option<&'a [u8]> {
+        record_field(self.line, self.ends, self.unquote, i)
+    }
+
+    /// Iterates over the columns
+    pub fn iter(&self) -> BytesColumns<'a> {
+        BytesColumns::new(self.line, self.ends).unquote(self.unquote)
+    }
+
+    /// Gets the whole parsed line, without its terminator, see
+    /// `Row::as_bytes`
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.line
+    }
+
+    /// Gets columns count
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// `BorrowedRecord` is empty if there is no columns
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+}
+
+impl<'a> IntoIterator for BorrowedRecord<'a> {
+    type Item = &'a [u8];
+    type IntoIter = BytesColumns<'a>;
+    fn into_iter(self) -> BytesColumns<'a> {
+        self.iter()
+    }
+}
+
+/// Gets the column at index `i` of `line`, `ends` being the end of each column
+fn record_field<'a>(line: &'a [u8], ends: &[usize], unquote: bool, i: usize) -> Option<&'a [u8]> {
+    ends.get(i).map(|&end| {
+        let start = if i == 0 { 0 } else { ends[i - 1] + 1 };
+        let s = &line[start..end];
+        if unquote && s.len() > 1 && s.starts_with(b"\"") && s.ends_with(b"\"") {
+            &s[1..s.len() - 1]
+        } else {
+            s
+        }
+    })
+}
+
+/// Iterator on owned `StringRecord`s, created by `Csv::records`
+pub struct Records<B: BufRead> {
+    csv: Csv<B>,
+}
+
+impl<B: BufRead> Iterator for Records<B> {
+    type Item = Result<StringRecord>;
+    fn next(&mut self) -> Option<Result<StringRecord>> {
+        self.csv.next().map(|r| r.and_then(|r| StringRecord::from_row(&r)))
+    }
+}
+
+/// Iterator on rows and their starting positions, created by
+/// `Csv::records_with_position`
+pub struct RecordsWithPosition<B: BufRead> {
+    csv: Csv<B>,
+}
+
+impl<B: BufRead> Iterator for RecordsWithPosition<B> {
+    type Item = (Position, Result<Row>);
+    fn next(&mut self) -> Option<(Position, Result<Row>)> {
+        let row = match self.csv.next() {
+            Some(row) => row,
+            None => return None,
+        };
+        Some((self.csv.record_start.clone(), row))
+    }
+}
+
+/// Iterator on owned columns, created by `Csv::into_owned_rows`
+pub struct OwnedRows<B: BufRead> {
+    csv: Csv<B>,
+}
+
+impl<B: BufRead> Iterator for OwnedRows<B> {
+    type Item = Result<Vec<String>>;
+    fn next(&mut self) -> Option<Result<Vec<String>>> {
+        self.csv.next().map(|r| r.and_then(|r| {
+            r.columns().map(|c| c.map(|c| c.to_string()).collect())
+        }))
+    }
+}
+
+/// Iterator on rows decoded into `T`, created by `Csv::into_deserialize`
+pub struct DeserializeIterator<B: BufRead, T> {
+    csv: Csv<B>,
+    _marker: PhantomData<T>,
+}
+
+impl<B: BufRead, T: Decodable> Iterator for DeserializeIterator<B, T> {
+    type Item = Result<T>;
+    fn next(&mut self) -> Option<Result<T>> {
+        self.csv.next().map(|r| r.and_then(|r| r.decode()))
     }
 
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.csv.size_hint()
+    }
 }
 
 /// Consumes bytes as long as they are within quotes
-/// manages "" as quote escape
+/// manages "" as quote escape, keeping only one quote if `unescape`
+/// sets `quote_closed` if the closing quote is the last byte of the buffer,
+/// the next buffer may start with an escaped quote
 /// returns
 /// - Ok(true) if entirely consumed
 /// - Ok(false) if no issue but it reached end of buffer
-/// - Err(Error::UnescapeQuote) if a quote if found within the column
+/// - Err(Error::UnescapeQuote) if a quote if found within the column, the
+///   reader being consumed up to the offending byte
 macro_rules! consume_quote {
-    ($bytes: expr, $delimiter: expr, $in_quote: expr,
-     $start: expr, $buf: expr, $available: expr, $quote_count: expr) => {
+    ($r: expr, $read: expr, $bytes: expr, $delimiter: expr, $terminator: expr,
+     $in_quote: expr, $start: expr, $buf: expr, $available: expr, $quote_count: expr,
+     $unescape: expr, $newline: expr, $quote_closed: expr) => {
         $in_quote = false;
         loop {
             match $bytes.next() {
+                Some((i, &b'\n')) if $newline == NewlineInQuotes::NormalizeLf => {
+                    // drop the `\r`, be it in this buffer or the previous one
+                    if i > $start && $available[i - 1] == b'\r' {
+                        $buf.extend_from_slice(&$available[$start..i - 1]);
+                        $start = i;
+                        $quote_count += 1;
+                    } else if i == 0 && $buf.last() == Some(&b'\r') {
+                        $buf.pop();
+                        $quote_count += 1;
+                    }
+                },
                 Some((_, &b'\"')) => {
                     match $bytes.clone().next() {
                         Some((i, &b'\"')) => {
                             $bytes.next(); // escaping quote
-                            $buf.extend_from_slice(&$available[$start..i]);
-                            $start = i + 1;
-                            $quote_count += 1;
+                            if $unescape {
+                                $buf.extend_from_slice(&$available[$start..i]);
+                                $start = i + 1;
+                                $quote_count += 1;
+                            }
+                        },
+                        None => {
+                            $quote_closed = true;
+                            break;
                         },
-                        None | Some((_, &b'\r')) | Some((_, &b'\n')) => break,
                         Some((_, d)) if *d == $delimiter => break,
-                        Some((_, _)) => return Err(Error::UnescapedQuote),
+                        Some((i, _)) if $terminator.follows_quote(&$available[i..]) => break,
+                        Some((i, _)) => {
+                            let (line, pos) = error_line($buf, $available, $start, i - 1);
+                            $r.consume(i);
+                            *$read += i;
+                            return Err(Error::UnescapedQuote { line: line, pos: pos, line_number: 0 });
+                        },
                     }
                 },
                 None => {
@@ -313,50 +2295,249 @@ macro_rules! consume_quote {
     }
 }
 
-/// Reads an entire line into memory
-fn read_line<R: BufRead>(r: &mut R, buf: &mut Vec<u8>,
-                         delimiter: u8, cols: &mut Vec<usize>) -> Result<usize>
+/// Settings of `parse_record`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseConfig {
+    /// column delimiter
+    pub delimiter: u8,
+    /// whether quotes are handled
+    pub quoting: bool,
+    /// whether doubled quotes are unescaped, surrounding quotes being kept
+    pub unescape: bool,
+    /// line terminator
+    pub terminator: Terminator,
+    /// handling of `\r\n` within quoted fields
+    pub newline_in_quotes: NewlineInQuotes,
+    /// maximum record size in bytes
+    pub max_record_size: usize,
+}
+
+impl Default for ParseConfig {
+    fn default() -> ParseConfig {
+        ParseConfig {
+            delimiter: b',',
+            quoting: true,
+            unescape: true,
+            terminator: Terminator::Crlf,
+            newline_in_quotes: NewlineInQuotes::Preserve,
+            max_record_size: usize::MAX,
+        }
+    }
+}
+
+/// Parses the next record of `reader`, as `Csv` does
+///
+/// `buf` and `cols` are cleared then filled with the record, without its
+/// terminator, and with the end of each of its columns in `buf`. Columns
+/// keep their surrounding quotes, `BytesColumns` strips them.
+///
+/// Returns the number of bytes consumed, 0 meaning the end of the input.
+/// An empty line is a single empty column. On quote errors, `reader` is
+/// consumed up to the offending byte and the error line number is 0, lines
+/// being counted by the caller.
+///
+/// # Example
+///
+/// ```rust
+/// use quick_csv::{parse_record, ParseConfig};
+/// use quick_csv::columns::BytesColumns;
+///
+/// let mut reader = "a,\"b,c\"\nd,e\n".as_bytes();
+/// let (mut buf, mut cols) = (Vec::new(), Vec::new());
+/// let config = ParseConfig::default();
+/// while parse_record(&mut reader, &mut buf, &mut cols, &config).unwrap() > 0 {
+///     println!("{:?}", BytesColumns::new(&buf, &cols).collect::<Vec<_>>());
+/// }
+/// ```
+pub fn parse_record<R: BufRead>(reader: &mut R, buf: &mut Vec<u8>, cols: &mut Vec<usize>,
+                                config: &ParseConfig) -> Result<usize>
 {
+    buf.clear();
+    cols.clear();
     let mut read = 0;
+    let any = Terminator::Any;
+    let terminator = if config.terminator == Terminator::Auto { &any } else { &config.terminator };
+    try!(read_line(reader, buf, config.delimiter, config.quoting, config.unescape,
+                   terminator, config.newline_in_quotes, config.max_record_size,
+                   cols, &mut read));
+    if read > 0 {
+        if config.terminator.strips_cr() && buf.ends_with(&[b'\r']) {
+            buf.pop();
+        }
+        cols.push(buf.len());
+    }
+    Ok(read)
+}
+
+/// Reads an entire line into memory
+///
+/// `read` is incremented by the number of bytes consumed, 0 meaning end of input.
+/// On quote errors, the reader is consumed up to the offending byte
+fn read_line<R: BufRead>(r: &mut R, buf: &mut Vec<u8>, delimiter: u8, quoting: bool,
+                         unescape: bool, terminator: &Terminator, newline: NewlineInQuotes,
+                         max_size: usize, cols: &mut Vec<usize>, read: &mut usize) -> Result<()>
+{
     let mut in_quote = false;
+    let mut check_lf = false;
+    // bytes ending the previous buffers which start the terminator sequence
+    let mut pending = Vec::new();
+    // the pending bytes directly follow a closing quote
+    let mut pending_quote = false;
+    let mut quote_start = 0;
     let mut done = false;
     let mut quote_count = 0;
+    // last byte of the previous buffer, to find field boundaries across refills
+    let mut last = None;
+    // previous buffer ended with a quote closing a field
+    let mut quote_closed = false;
     while !done {
         let used = {
             let available = match r.fill_buf() {
-                Ok(n) if n.is_empty() => return Ok(read),
+                Ok(n) if n.is_empty() && in_quote => {
+                    return Err(Error::Parse(format!(
+                        "unterminated quoted field in column {} starting at byte {} of the \
+                         record", cols.len(), quote_start)));
+                },
+                Ok(n) if n.is_empty() && pending_quote => {
+                    let pos = buf.len() - pending.len() - 1;
+                    return Err(Error::UnescapedQuote { line: buf.clone(), pos: pos,
+                                                       line_number: 0 });
+                },
+                Ok(n) if n.is_empty() => return Ok(()),
                 Ok(n) => n,
                 Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
                 Err(e) => return Err(Error::from(e)),
             };
 
+            // previous buffers ended with the start of the terminator sequence, it
+            // may end in this buffer from any of the pending bytes
+            if !pending.is_empty() {
+                if let Terminator::Bytes(ref seq) = *terminator {
+                    let mut found = None;
+                    for k in 0..pending.len() {
+                        if !seq.starts_with(&pending[k..]) {
+                            continue;
+                        }
+                        let rest = &seq[pending.len() - k..];
+                        if available.starts_with(rest) {
+                            found = Some((k, Some(rest.len())));
+                            break;
+                        } else if rest.starts_with(available) {
+                            found = Some((k, None));
+                            break;
+                        }
+                    }
+                    if pending_quote && !matches!(found, Some((0, _))) {
+                        // the closing quote is not followed by the terminator
+                        let (line, pos) = error_line(buf, available, 0, 0);
+                        let pos = pos - pending.len() - 1;
+                        return Err(Error::UnescapedQuote { line: line, pos: pos, line_number: 0 });
+                    }
+                    match found {
+                        Some((k, Some(used))) => {
+                            let len = buf.len() + k - pending.len();
+                            buf.truncate(len);
+                            r.consume(used);
+                            *read += used;
+                            return Ok(());
+                        },
+                        Some((k, None)) => {
+                            let used = available.len();
+                            buf.extend_from_slice(available);
+                            pending.drain(..k);
+                            pending.extend_from_slice(available);
+                            last = available.last().cloned();
+                            r.consume(used);
+                            *read += used;
+                            continue;
+                        },
+                        None => (),
+                    }
+                }
+                pending.clear();
+                pending_quote = false;
+            }
+
             let mut bytes = available.iter().enumerate();
             let mut start = 0;
 
+            // a quote ending the previous buffer is either an escape or the end
+            // of the field
+            if quote_closed {
+                quote_closed = false;
+                match available[0] {
+                    b'\"' => {
+                        bytes.next(); // escaping quote
+                        if unescape {
+                            start = 1;
+                            quote_count += 1;
+                        }
+                        consume_quote!(r, read, bytes, delimiter, terminator, in_quote, start,
+                                       buf, available, quote_count, unescape, newline,
+                                       quote_closed);
+                    },
+                    d if d == delimiter => (),
+                    _ if terminator.follows_quote(available) => (),
+                    _ => {
+                        let (line, pos) = error_line(buf, available, 0, 0);
+                        return Err(Error::UnescapedQuote { line: line, pos: pos - 1, line_number: 0 });
+                    },
+                }
+            }
+
             // previous buffer was exhausted without exiting from quotes
             if in_quote {
-                consume_quote!(bytes, delimiter, in_quote, start, buf, available, quote_count);
+                consume_quote!(r, read, bytes, delimiter, terminator, in_quote, start,
+                               buf, available, quote_count, unescape, newline, quote_closed);
             }
 
             // use a simple loop instead of for loop to allow nested loop
             let used: usize;
             loop {
-                match bytes.next() {
-                    Some((i, &b'\"')) => {
-                        if i == 0 || available[i - 1] == delimiter {
-                            consume_quote!(bytes, delimiter, in_quote, start, buf, available, quote_count);
+                match next_special(&mut bytes, available, delimiter, quoting, terminator) {
+                    Some((i, &b'\"')) if quoting => {
+                        let previous = if i == 0 { last } else { Some(available[i - 1]) };
+                        if previous.map_or(true, |p| p == delimiter) {
+                            quote_start = *read + i;
+                            consume_quote!(r, read, bytes, delimiter, terminator, in_quote, start,
+                               buf, available, quote_count, unescape, newline, quote_closed);
                         } else {
-                            return Err(Error::UnexpextedQuote);
+                            let (line, pos) = error_line(buf, available, start, i);
+                            r.consume(i);
+                            *read += i;
+                            return Err(Error::UnexpectedQuote { line: line, pos: pos, line_number: 0 });
                         }
                     },
-                    Some((i, &b'\n')) => {
+                    Some((i, &b)) if terminator.is_end(b) => {
+                        let len = match *terminator {
+                            Terminator::Any if b == b'\r' && available.get(i + 1) == Some(&b'\n') => {
+                                // let the line feed terminate the line
+                                continue;
+                            },
+                            Terminator::Bytes(ref seq) if !available[i..].starts_with(seq) => {
+                                if seq.starts_with(&available[i..]) {
+                                    // the sequence may end in the next buffer
+                                    pending.extend_from_slice(&available[i..]);
+                                    let previous = if i == 0 { last } else { Some(available[i - 1]) };
+                                    pending_quote = quoting && previous == Some(b'\"');
+                                    used = available.len();
+                                    buf.extend_from_slice(&available[start..used]);
+                                    break;
+                                }
+                                continue;
+                            },
+                            Terminator::Bytes(ref seq) => seq.len(),
+                            _ => 1,
+                        };
                         done = true;
-                        used = i + 1;
+                        used = i + len;
+                        check_lf = *terminator == Terminator::Any && b == b'\r'
+                            && used == available.len();
                         buf.extend_from_slice(&available[start..i]);
                         break;
                     },
                     Some((i, &d)) => {
-                        if d == delimiter { cols.push(read + i - quote_count); }
+                        if d == delimiter { cols.push(*read + i - quote_count); }
                     },
                     None => {
                         used = available.len();
@@ -365,18 +2546,404 @@ fn read_line<R: BufRead>(r: &mut R, buf: &mut Vec<u8>,
                     },
                 }
             }
+            if used > 0 {
+                last = Some(available[used - 1]);
+            }
+            used
+        };
+        r.consume(used);
+        *read += used;
+        if buf.len() > max_size {
+            return Err(Error::RecordTooLarge(max_size));
+        }
+    }
+    if check_lf {
+        // a `\r` ended the buffer, it may be followed by a `\n`
+        *read += try!(consume_lf(r));
+    }
+    Ok(())
+}
+
+/// Consumes a `\n` if it is the next byte
+fn consume_lf<R: BufRead>(r: &mut R) -> Result<usize> {
+    loop {
+        let lf = match r.fill_buf() {
+            Ok(n) => n.starts_with(b"\n"),
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(Error::from(e)),
+        };
+        if lf {
+            r.consume(1);
+            return Ok(1);
+        }
+        return Ok(0);
+    }
+}
+
+/// Consumes everything up to and including the next line terminator
+fn skip_line<R: BufRead>(r: &mut R, terminator: &Terminator) -> Result<usize> {
+    let mut read = 0;
+    // last bytes read, to find a `Terminator::Bytes` sequence
+    let mut window = Vec::new();
+    loop {
+        let (done, used) = {
+            let available = match r.fill_buf() {
+                Ok(n) if n.is_empty() => return Ok(read),
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(Error::from(e)),
+            };
+            let end = match *terminator {
+                Terminator::Bytes(ref seq) => {
+                    available.iter().position(|&b| ends_sequence(&mut window, seq, b))
+                }
+                _ => available.iter().position(|&b| terminator.is_end(b)),
+            };
+            match end {
+                Some(i) => (Some(available[i]), i + 1),
+                None => (None, available.len()),
+            }
+        };
+        r.consume(used);
+        read += used;
+        match done {
+            Some(b'\r') if *terminator == Terminator::Any => return Ok(read + try!(consume_lf(r))),
+            Some(_) => return Ok(read),
+            None => (),
+        }
+    }
+}
+
+/// Consumes the reader, counting lines not within quotes, up to `limit` lines
+///
+/// Returns the lines count and the bytes read, or an `Error::Parse` if the
+/// input ends within quotes, `line` being the line number of the reader
+fn count_lines<R: BufRead>(r: &mut R, quoting: bool, terminator: &Terminator,
+                           skip_empty_lines: bool, limit: usize, line: usize)
+    -> Result<(usize, usize)>
+{
+    let mut count = 0;
+    let mut read = 0;
+    let mut in_quote = false;
+    // offsets of the current line and of its last opening quote
+    let mut line_start = 0;
+    let mut quote_start = 0;
+    // the current line has bytes, and bytes other than a stripped `\r`
+    let mut started = false;
+    let mut empty = true;
+    let mut after_cr = false;
+    // last bytes read, to find a `Terminator::Bytes` sequence
+    let mut window = Vec::new();
+    let mut stop = false;
+    loop {
+        let used = {
+            let available = match r.fill_buf() {
+                Ok(n) if n.is_empty() => break,
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(Error::from(e)),
+            };
+            let mut used = available.len();
+            for (i, &b) in available.iter().enumerate() {
+                if quoting && b == b'\"' {
+                    if !in_quote {
+                        quote_start = read + i;
+                    }
+                    in_quote = !in_quote;
+                    started = true;
+                    empty = false;
+                    after_cr = false;
+                    window.clear();
+                    continue;
+                }
+                if in_quote {
+                    continue;
+                }
+                let end = match *terminator {
+                    Terminator::Crlf => b == b'\n',
+                    Terminator::Cr => b == b'\r',
+                    Terminator::Any | Terminator::Auto => b == b'\r' || (b == b'\n' && !after_cr),
+                    Terminator::Byte(t) => b == t,
+                    Terminator::Bytes(ref seq) => ends_sequence(&mut window, seq, b),
+                };
+                let cr = after_cr;
+                after_cr = b == b'\r';
+                if end {
+                    line_start = read + i + 1;
+                    if !(skip_empty_lines && empty) {
+                        count += 1;
+                    }
+                    started = false;
+                    empty = true;
+                    window.clear();
+                    if count == limit {
+                        used = i + 1;
+                        stop = true;
+                        break;
+                    }
+                } else if !(terminator.strips_cr() && b == b'\n' && cr) {
+                    // not the `\n` of `\r\n` in `Any` mode
+                    started = true;
+                    // a single `\r` ending the line is stripped in `Crlf` mode
+                    if !(terminator.strips_cr() && b == b'\r' && !cr) {
+                        empty = false;
+                    }
+                }
+            }
             used
         };
         r.consume(used);
         read += used;
+        if stop {
+            if after_cr && *terminator == Terminator::Any {
+                read += try!(consume_lf(r));
+            }
+            return Ok((count, read));
+        }
     }
-    Ok(read)
+    if in_quote {
+        return Err(Error::Parse(format!(
+            "line {}: unterminated quoted field starting at byte {} of the record",
+            line + count + 1, quote_start - line_start)));
+    }
+    if started && !(skip_empty_lines && empty) {
+        // last line without terminator
+        count += 1;
+    }
+    Ok((count, read))
 }
 
-fn try_consume_utf8_bom<B: BufRead>(reader: &mut B) -> Result<()> {
+/// Checks that the delimiter, quote, terminator and number separators bytes
+/// do not conflict and that the integer radix is valid, see
+/// `CsvBuilder::validate`
+fn validate_settings(delimiter: u8, quoting: bool, terminator: &Terminator,
+                     settings: &DecodeSettings) -> Result<()>
+{
+    let invalid = |msg: &str| Err(Error::InvalidConfig(msg.to_string()));
+    if quoting && delimiter == b'\"' {
+        return invalid("the delimiter is the quote byte");
+    }
+    if terminator.contains(delimiter) {
+        return invalid("the delimiter is part of the line terminator");
+    }
+    if quoting && terminator.contains(b'\"') {
+        return invalid("the quote byte is part of the line terminator");
+    }
+    let decimal = settings.decimal_separator.unwrap_or(b'.');
+    if settings.thousands_separator == Some(decimal) {
+        return invalid("the thousands separator is the decimal separator");
+    }
+    match settings.integer_radix {
+        Some(radix) if !(2..=36).contains(&radix) => {
+            Err(Error::InvalidConfig(format!("the integer radix {} is not in the range 2 to 36",
+                                             radix)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Appends `b` to `window`, the last bytes read, and checks if they end with
+/// `seq`
+///
+/// Unlike counting the bytes matched so far, this finds self-overlapping
+/// sequences, e.g. `~~|` in `~~~|`. An empty sequence never matches.
+fn ends_sequence(window: &mut Vec<u8>, seq: &[u8], b: u8) -> bool {
+    if seq.is_empty() {
+        return false;
+    }
+    if window.len() == seq.len() {
+        window.remove(0);
+    }
+    window.push(b);
+    window[..] == *seq
+}
+
+/// Builds a new line and columns ends with only the `indices` columns
+///
+/// Returns `None` if any index is out of range
+fn select_columns(line: &[u8], cols: &[usize], indices: &[usize], delimiter: u8)
+    -> Option<(Vec<u8>, Vec<usize>)>
+{
+    let mut buf = Vec::with_capacity(line.len());
+    let mut ends = Vec::with_capacity(indices.len());
+    for &i in indices {
+        let end = match cols.get(i) {
+            Some(&end) => end,
+            None => return None,
+        };
+        let start = if i == 0 { 0 } else { cols[i - 1] + 1 };
+        if !ends.is_empty() {
+            buf.push(delimiter);
+        }
+        buf.extend_from_slice(&line[start..end]);
+        ends.push(buf.len());
+    }
+    Some((buf, ends))
+}
+
+/// Merges the columns from the `n`th one into a single column
+///
+/// When `unquote`, columns are unquoted then the merged one is quoted
+fn merge_columns(line: &mut Vec<u8>, cols: &mut Vec<usize>, n: usize, delimiter: u8,
+                 unquote: bool) {
+    if unquote {
+        let start = if n > 1 { cols[n - 2] + 1 } else { 0 };
+        let mut merged = vec![b'\"'];
+        for (i, col) in BytesColumns::new(line, cols).skip(n - 1).enumerate() {
+            if i > 0 {
+                merged.push(delimiter);
+            }
+            merged.extend_from_slice(col);
+        }
+        merged.push(b'\"');
+        line.truncate(start);
+        line.extend_from_slice(&merged);
+    }
+    cols.truncate(n);
+    cols[n - 1] = line.len();
+}
+
+/// Removes the last column, along with its delimiter, if it is empty
+///
+/// A quoted empty column is not empty in `line` as quotes are kept
+fn trim_trailing_empty(line: &mut Vec<u8>, cols: &mut Vec<usize>) {
+    let n = cols.len();
+    if n > 1 && cols[n - 1] == cols[n - 2] + 1 {
+        cols.pop();
+        line.truncate(cols[n - 2]);
+    }
+}
+
+/// Removes empty columns, `cols` being the delimiters positions
+///
+/// Returns the new line and delimiters positions
+fn collapse_delimiters(line: &[u8], cols: &[usize], delimiter: u8) -> (Vec<u8>, Vec<usize>) {
+    let mut buf = Vec::with_capacity(line.len());
+    let mut delimiters = Vec::with_capacity(cols.len());
+    let mut start = 0;
+    for &end in cols.iter().chain(Some(line.len()).iter()) {
+        if end > start {
+            if !buf.is_empty() {
+                delimiters.push(buf.len());
+                buf.push(delimiter);
+            }
+            buf.extend_from_slice(&line[start..end]);
+        }
+        start = end + 1;
+    }
+    (buf, delimiters)
+}
+
+/// Builds a new line and columns ends with `f` applied on every column
+fn map_fields(line: &[u8], ends: &[usize], unquote: bool, delimiter: u8,
+              f: &dyn Fn(&str) -> Cow<str>) -> (Vec<u8>, Vec<usize>)
+{
+    let mut buf = Vec::with_capacity(line.len());
+    let mut cols = Vec::with_capacity(ends.len());
+    for col in BytesColumns::new(line, ends).unquote(unquote) {
+        if !cols.is_empty() {
+            buf.push(delimiter);
+        }
+        match ::std::str::from_utf8(col) {
+            Ok(s) => buf.extend_from_slice(f(s).as_bytes()),
+            Err(_) => buf.extend_from_slice(col),
+        }
+        cols.push(buf.len());
+    }
+    (buf, cols)
+}
+
+/// Replaces invalid UTF-8 sequences of every column, quotes included, with
+/// `U+FFFD REPLACEMENT CHARACTER`
+fn lossy_fields(line: &[u8], ends: &[usize], delimiter: u8) -> (Vec<u8>, Vec<usize>) {
+    let mut buf = Vec::with_capacity(line.len() + 8);
+    let mut cols = Vec::with_capacity(ends.len());
+    for col in BytesColumns::new(line, ends).unquote(false) {
+        if !cols.is_empty() {
+            buf.push(delimiter);
+        }
+        buf.extend_from_slice(String::from_utf8_lossy(col).as_bytes());
+        cols.push(buf.len());
+    }
+    (buf, cols)
+}
+
+/// Advances `bytes` to the next line terminator, delimiter or quote if `quoting`
+#[cfg(feature = "memchr")]
+#[inline]
+fn next_special<'a>(bytes: &mut Enumerate<Iter<'a, u8>>, available: &[u8],
+                    delimiter: u8, quoting: bool, terminator: &Terminator)
+    -> Option<(usize, &'a u8)>
+{
+    let start = available.len() - bytes.len();
+    let haystack = &available[start..];
+    let end = terminator.end_byte();
+    let mut found = if quoting {
+        memchr::memchr3(delimiter, end, b'\"', haystack)
+    } else {
+        memchr::memchr2(delimiter, end, haystack)
+    };
+    if *terminator == Terminator::Any {
+        let cr = memchr::memchr(b'\r', &haystack[..found.unwrap_or(haystack.len())]);
+        found = cr.or(found);
+    }
+    match found {
+        Some(i) => bytes.nth(i),
+        None => bytes.nth(available.len()),
+    }
+}
+
+/// Advances `bytes` to the next byte
+#[cfg(not(feature = "memchr"))]
+#[inline]
+fn next_special<'a>(bytes: &mut Enumerate<Iter<'a, u8>>, _: &[u8], _: u8, _: bool,
+                    _: &Terminator) -> Option<(usize, &'a u8)>
+{
+    bytes.next()
+}
+
+/// Gets the record content being parsed when an error is found at `i`
+/// and the position of the error within it
+fn error_line(buf: &[u8], available: &[u8], start: usize, i: usize) -> (Vec<u8>, usize) {
+    let end = available[i..].iter().position(|&b| b == b'\n').map_or(available.len(), |p| i + p);
+    let mut line = buf.to_vec();
+    let pos = line.len() + i - start;
+    line.extend_from_slice(&available[start..end]);
+    (line, pos)
+}
+
+/// Gets the size of a file, if it is a regular file with a non zero length
+///
+/// Special files, e.g. in `/proc`, may have a zero length while not being
+/// empty
+fn file_len(metadata: &::std::fs::Metadata) -> Option<u64> {
+    if metadata.is_file() && metadata.len() > 0 {
+        Some(metadata.len())
+    } else {
+        None
+    }
+}
+
+/// Guesses the terminator from the first line end of the reader buffer
+fn detect_terminator<B: BufRead>(reader: &mut B) -> Result<Terminator> {
+    let available = try!(reader.fill_buf());
+    let terminator = match available.iter().position(|&b| b == b'\n' || b == b'\r') {
+        Some(i) if available[i] == b'\n' => Terminator::Byte(b'\n'),
+        Some(i) => match available.get(i + 1) {
+            Some(&b'\n') => Terminator::Crlf,
+            Some(_) => Terminator::Cr,
+            None => Terminator::Any,
+        },
+        None => Terminator::Any,
+    };
+    Ok(terminator)
+}
+
+fn try_consume_utf8_bom<B: BufRead>(reader: &mut B) -> Result<usize> {
     if try!(reader.fill_buf()).starts_with(UTF8_BOM) {
         reader.consume(UTF8_BOM.len());
+        return Ok(UTF8_BOM.len());
     }
 
-    Ok(())
+    Ok(0)
 }
@@ -0,0 +1,177 @@
+//! Multiple files module
+//!
+//! Provides `MultiFileReader`, to read several csv files as a single one
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use {file_len, skip_line, Terminator, UTF8_BOM};
+
+/// Reader over the concatenation of several files
+///
+/// Files are opened one after the other, when the previous one is exhausted.
+/// A line terminator is inserted after a file not ending with one, and the
+/// UTF-8 BOM of every file but the first is dropped.
+///
+/// If `skip_headers` is set, the first line of every file but the first
+/// non empty one is dropped as well, e.g. for monthly exports repeating
+/// their header. The header line is not parsed, a quoted line break in it
+/// is not supported.
+pub struct MultiFileReader {
+    paths: Vec<PathBuf>,
+    /// index of the next file to open
+    next: usize,
+    current: Option<BufReader<File>>,
+    skip_headers: bool,
+    terminator: Terminator,
+    /// bytes inserted after a file not ending with a line terminator
+    line_end: Vec<u8>,
+    /// bytes of `line_end` still to be read, if any
+    pending: Option<usize>,
+    /// last bytes read, as many as in `line_end`
+    last: Vec<u8>,
+}
+
+impl MultiFileReader {
+
+    /// Creates a new MultiFileReader, the files being opened lazily
+    pub fn new<P: AsRef<Path>>(paths: &[P], skip_headers: bool) -> MultiFileReader {
+        MultiFileReader {
+            paths: paths.iter().map(|p| p.as_ref().to_path_buf()).collect(),
+            next: 0,
+            current: None,
+            skip_headers: skip_headers,
+            terminator: Terminator::Crlf,
+            line_end: b"\n".to_vec(),
+            pending: None,
+            last: Vec::new(),
+        }
+    }
+
+    /// Sets the line terminator of the files (default: `Terminator::Crlf`)
+    ///
+    /// Used to find the header line to skip, and inserted after a file not
+    /// ending with one. `Terminator::Auto` is read as `Terminator::Any`.
+    pub fn terminator(mut self, terminator: Terminator) -> MultiFileReader {
+        self.line_end = match terminator {
+            Terminator::Cr => b"\r".to_vec(),
+            Terminator::Byte(t) => vec![t],
+            Terminator::Bytes(ref seq) => seq.clone(),
+            _ => b"\n".to_vec(),
+        };
+        self.terminator = match terminator {
+            Terminator::Auto => Terminator::Any,
+            t => t,
+        };
+        self
+    }
+
+    /// Gets the path of the file being read, if any
+    pub fn current_path(&self) -> Option<&Path> {
+        if self.current.is_some() {
+            Some(&self.paths[self.next - 1])
+        } else {
+            None
+        }
+    }
+
+    /// Gets the maximum number of bytes read, the size of the files plus the
+    /// line terminator possibly inserted after each of them
+    ///
+    /// Returns `None` if the size of a file is unknown
+    pub fn max_len(&self) -> io::Result<Option<u64>> {
+        let mut len = Some(0);
+        for path in &self.paths {
+            let file = file_len(&try!(path.metadata()));
+            len = len.and_then(|l| file.map(|f| l + f + self.line_end.len() as u64));
+        }
+        Ok(len)
+    }
+
+    /// Opens the next file, dropping its BOM and header if needed
+    fn open_next(&mut self) -> io::Result<()> {
+        let mut file = BufReader::new(try!(File::open(&self.paths[self.next])));
+        if self.next > 0 && try!(file.fill_buf()).starts_with(UTF8_BOM) {
+            file.consume(UTF8_BOM.len());
+        }
+        if self.skip_headers && !self.last.is_empty() {
+            try!(skip_line(&mut file, &self.terminator));
+        }
+        self.next += 1;
+        self.current = Some(file);
+        Ok(())
+    }
+
+    /// Checks if the bytes read so far end with a line terminator
+    fn ends_line(&self) -> bool {
+        match self.terminator {
+            Terminator::Bytes(ref seq) => seq.is_empty() || self.last.ends_with(seq),
+            ref t => self.last.last().is_some_and(|&b| t.contains(b)),
+        }
+    }
+}
+
+/// Appends `bytes` to `last`, keeping its `len` last bytes only
+fn push_last(last: &mut Vec<u8>, bytes: &[u8], len: usize) {
+    let len = ::std::cmp::max(len, 1);
+    last.extend_from_slice(&bytes[bytes.len().saturating_sub(len)..]);
+    let extra = last.len().saturating_sub(len);
+    last.drain(..extra);
+}
+
+impl BufRead for MultiFileReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        loop {
+            if self.pending.is_some() {
+                break;
+            }
+            if self.current.is_none() {
+                if self.next == self.paths.len() {
+                    return Ok(&[]);
+                }
+                try!(self.open_next());
+            }
+            if !try!(self.current.as_mut().unwrap().fill_buf()).is_empty() {
+                break;
+            }
+            self.current = None;
+            if !self.last.is_empty() && !self.ends_line() {
+                self.pending = Some(0);
+            }
+        }
+        match self.pending {
+            Some(i) => Ok(&self.line_end[i..]),
+            None => self.current.as_mut().unwrap().fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt == 0 {
+            return;
+        }
+        if let Some(i) = self.pending {
+            push_last(&mut self.last, &self.line_end[i..i + amt], self.line_end.len());
+            let i = i + amt;
+            self.pending = if i < self.line_end.len() { Some(i) } else { None };
+            return;
+        }
+        if let Some(ref mut file) = self.current {
+            push_last(&mut self.last, &file.buffer()[..amt], self.line_end.len());
+            file.consume(amt);
+        }
+    }
+}
+
+impl Read for MultiFileReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let available = try!(self.fill_buf());
+            let n = ::std::cmp::min(buf.len(), available.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
@@ -0,0 +1,100 @@
+//! Schema inference module
+//!
+//! Provides `ColumnType`, guessed from a sample of rows by `Csv::infer_schema`
+//! and checked against all rows by `Csv::validate_schema`
+
+use columns::DecodeSettings;
+
+/// Type of the values of a column, from the most to the least specific
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    /// Only empty or null values seen so far
+    Unknown,
+    /// Booleans, including `DecodeSettings` tokens
+    Bool,
+    /// Integers, fitting in an `i64`
+    Integer,
+    /// Floats, or a mix of integers and floats
+    Float,
+    /// Anything else
+    String,
+}
+
+impl FieldType {
+
+    /// Guesses the type of a single non null value
+    fn of(value: &str, settings: &DecodeSettings) -> FieldType {
+        if value.parse::<bool>().is_ok()
+            || settings.true_tokens.iter().chain(settings.false_tokens.iter()).any(|t| t == value)
+        {
+            FieldType::Bool
+        } else if settings.normalize_number(value, false).parse::<i64>().is_ok() {
+            FieldType::Integer
+        } else if settings.normalize_number(value, true).parse::<f64>().is_ok() {
+            FieldType::Float
+        } else {
+            FieldType::String
+        }
+    }
+
+    /// Gets the narrowest type compatible with both `self` and `other`
+    fn widen(self, other: FieldType) -> FieldType {
+        match (self, other) {
+            (FieldType::Unknown, t) | (t, FieldType::Unknown) => t,
+            (a, b) if a == b => a,
+            (FieldType::Integer, FieldType::Float) | (FieldType::Float, FieldType::Integer) => {
+                FieldType::Float
+            }
+            _ => FieldType::String,
+        }
+    }
+
+}
+
+/// Inferred type of a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnType {
+    /// type of the non null values
+    pub field_type: FieldType,
+    /// whether some values are null or missing
+    pub nullable: bool,
+}
+
+impl ColumnType {
+
+    /// Creates a `ColumnType` before any value is seen
+    pub fn new() -> ColumnType {
+        ColumnType {
+            field_type: FieldType::Unknown,
+            nullable: false,
+        }
+    }
+
+    /// Widens the type to accept `value`
+    pub fn update(&mut self, value: &str, settings: &DecodeSettings) {
+        if settings.is_null(value) {
+            self.nullable = true;
+        } else {
+            self.field_type = self.field_type.widen(FieldType::of(value, settings));
+        }
+    }
+
+    /// Checks if `value` is of this type, i.e. if `update` would leave the
+    /// type unchanged
+    ///
+    /// Integers are valid floats and any value is a valid string.
+    pub fn accepts(&self, value: &str, settings: &DecodeSettings) -> bool {
+        if settings.is_null(value) {
+            self.nullable
+        } else {
+            self.field_type.widen(FieldType::of(value, settings)) == self.field_type
+        }
+    }
+
+}
+
+impl Default for ColumnType {
+    fn default() -> ColumnType {
+        ColumnType::new()
+    }
+}
@@ -1,5 +1,9 @@
-use Csv;
-use std::io::{self, Read, Seek};
+#![allow(deprecated)]
+
+use {parse_record, ByteRecord, Csv, CsvBuilder, ExtraColumns, NewlineInQuotes, OnError, ParseConfig,
+     Position, Terminator, Utf8Mode};
+use writer::CsvWriter;
+use std::io::{self, BufRead, Read, Seek};
 
 fn assert_svec_eq<S, T>(got: Vec<Vec<S>>, expected: Vec<Vec<T>>)
         where S: AsRef<str>, T: AsRef<str> {
@@ -106,28 +110,59 @@ parses_to!(empty_string_headers, "", vec![],
             |rdr: Csv<_>| rdr.has_header(true));
 parses_to!(empty_lines, "\n\n\n\n", vec![vec![""], vec![""], vec![""], vec![""]]);
 
-// The following tests are done in xml-rs and left commented because 
-// the expected behavior is not clear
-//
-// parses_to!(trailing_lines_no_record,
-//            "\n\n\na,b,c\nx,y,z\n\n\n",
-//            vec![vec!["a", "b", "c"], vec!["x", "y", "z"]]);
-// parses_to!(trailing_lines_no_record_crlf,
-//            "\r\n\r\n\r\na,b,c\r\nx,y,z\r\n\r\n\r\n",
-//            vec![vec!["a", "b", "c"], vec!["x", "y", "z"]]);
-// parses_to!(empty_lines_interspersed, "\n\na,b\n\n\nx,y\n\n\nm,n\n",
-//            vec![vec!["a", "b"], vec!["x", "y"], vec!["m", "n"]]);
-// parses_to!(empty_lines_crlf, "\r\n\r\n\r\n\r\n", vec![]);
-// parses_to!(empty_lines_interspersed_crlf,
-//            "\r\n\r\na,b\r\n\r\n\r\nx,y\r\n\r\n\r\nm,n\r\n",
-//            vec![vec!["a", "b"], vec!["x", "y"], vec!["m", "n"]]);
-// parses_to!(empty_lines_mixed, "\r\n\n\r\n\n", vec![]);
-// parses_to!(empty_lines_interspersed_mixed,
-//            "\n\r\na,b\r\n\n\r\nx,y\r\n\n\r\nm,n\r\n",
-//            vec![vec!["a", "b"], vec!["x", "y"], vec!["m", "n"]]);
-// parses_to!(empty_lines_cr, "\r\r\r\r", vec![]);
-// parses_to!(empty_lines_interspersed_cr, "\r\ra,b\r\r\rx,y\r\r\rm,n\r",
-//            vec![vec!["a", "b"], vec!["x", "y"], vec!["m", "n"]]);
+parses_to!(trailing_lines_no_record,
+           "\n\n\na,b,c\nx,y,z\n\n\n",
+           vec![vec!["a", "b", "c"], vec!["x", "y", "z"]],
+           |rdr: Csv<_>| rdr.skip_empty_lines(true));
+parses_to!(trailing_lines_no_record_crlf,
+           "\r\n\r\n\r\na,b,c\r\nx,y,z\r\n\r\n\r\n",
+           vec![vec!["a", "b", "c"], vec!["x", "y", "z"]],
+           |rdr: Csv<_>| rdr.skip_empty_lines(true));
+parses_to!(empty_lines_interspersed, "\n\na,b\n\n\nx,y\n\n\nm,n\n",
+           vec![vec!["a", "b"], vec!["x", "y"], vec!["m", "n"]],
+           |rdr: Csv<_>| rdr.skip_empty_lines(true));
+parses_to!(empty_lines_crlf, "\r\n\r\n\r\n\r\n", vec![],
+           |rdr: Csv<_>| rdr.skip_empty_lines(true));
+parses_to!(empty_lines_interspersed_crlf,
+           "\r\n\r\na,b\r\n\r\n\r\nx,y\r\n\r\n\r\nm,n\r\n",
+           vec![vec!["a", "b"], vec!["x", "y"], vec!["m", "n"]],
+           |rdr: Csv<_>| rdr.skip_empty_lines(true));
+parses_to!(empty_lines_mixed, "\r\n\n\r\n\n", vec![],
+           |rdr: Csv<_>| rdr.skip_empty_lines(true));
+parses_to!(empty_lines_interspersed_mixed,
+           "\n\r\na,b\r\n\n\r\nx,y\r\n\n\r\nm,n\r\n",
+           vec![vec!["a", "b"], vec!["x", "y"], vec!["m", "n"]],
+           |rdr: Csv<_>| rdr.skip_empty_lines(true));
+parses_to!(empty_lines_quoted, "\n\"\"\n\n", vec![vec![""]],
+           |rdr: Csv<_>| rdr.skip_empty_lines(true));
+
+parses_to!(empty_lines_cr, "\r\r\r\r", vec![],
+           |rdr: Csv<_>| rdr.skip_empty_lines(true).terminator(Terminator::Any));
+parses_to!(empty_lines_interspersed_cr, "\r\ra,b\r\r\rx,y\r\r\rm,n\r",
+           vec![vec!["a", "b"], vec!["x", "y"], vec!["m", "n"]],
+           |rdr: Csv<_>| rdr.skip_empty_lines(true).terminator(Terminator::Any));
+
+parses_to!(terminator_cr, "a\rb", vec![vec!["a"], vec!["b"]],
+           |rdr: Csv<_>| rdr.terminator(Terminator::Cr));
+parses_to!(terminator_cr_lf, "a,b\nc\rd,e", vec![vec!["a", "b\nc"], vec!["d", "e"]],
+           |rdr: Csv<_>| rdr.terminator(Terminator::Cr));
+parses_to!(terminator_any, "a\r\nb\rc\n", vec![vec!["a"], vec!["b"], vec!["c"]],
+           |rdr: Csv<_>| rdr.terminator(Terminator::Any));
+parses_to!(terminator_any_quoted, "\"a\rb\"\r\"c\"\r\n", vec![vec!["a\rb"], vec!["c"]],
+           |rdr: Csv<_>| rdr.terminator(Terminator::Any));
+parses_to!(terminator_byte, "a,b\x1ec\n,d\x1e", vec![vec!["a", "b"], vec!["c\n", "d"]],
+           |rdr: Csv<_>| rdr.terminator(Terminator::Byte(b'\x1e')));
+parses_to!(terminator_byte_quoted, "\"a\x1eb\",c\x1ed,e", vec![vec!["a\x1eb", "c"], vec!["d", "e"]],
+           |rdr: Csv<_>| rdr.terminator(Terminator::Byte(b'\x1e')));
+parses_to!(terminator_byte_quoted_end, "\"a\",b\x1ec,\"d\"\x1e", vec![vec!["a", "b"], vec!["c", "d"]],
+           |rdr: Csv<_>| rdr.terminator(Terminator::Byte(b'\x1e')));
+parses_to!(terminator_bytes, "a,b|~c,d|e|~", vec![vec!["a", "b"], vec!["c", "d|e"]],
+           |rdr: Csv<_>| rdr.terminator(Terminator::Bytes(b"|~".to_vec())));
+parses_to!(terminator_bytes_quoted_end, "x,\"a\"|~b,c|~", vec![vec!["x", "a"], vec!["b", "c"]],
+           |rdr: Csv<_>| rdr.terminator(Terminator::Bytes(b"|~".to_vec())));
+fail_parses_to!(terminator_bytes_quoted_partial, "x,\"a\"|b,c|~", vec![vec!["x", "a|b"], vec!["c"]],
+                |rdr: Csv<_>| rdr.terminator(Terminator::Bytes(b"|~".to_vec())));
+parses_to!(terminator_crlf_lone_cr, "a\rb\nc", vec![vec!["a\rb"], vec!["c"]]);
 
 parses_to!(quote_empty, "\"\"", vec![vec![""]]);
 parses_to!(quote_lf, "\"\"\n", vec![vec![""]]);
@@ -137,8 +172,25 @@ fail_parses_to!(quote_outer_space, "  \"a\"  ", vec![vec!["  \"a\"  "]]);
 parses_to!(quote_inner_quote, "a,b,\"c\"\"d\",e", vec![vec!["a", "b", "c\"d", "e"]]);
 fail_parses_to!(inner_quote_without_quoted_column, "a,b,c\"\"d,e", vec![vec!["a", "b", "c\"d", "e"]]);
 
+parses_to!(no_quoting, "a\"b,c", vec![vec!["a\"b", "c"]],
+           |rdr: Csv<_>| rdr.quoting(false));
+parses_to!(no_quoting_quoted, "\"a\",\"b,c\"", vec![vec!["\"a\"", "\"b", "c\""]],
+           |rdr: Csv<_>| rdr.quoting(false));
+parses_to!(tsv_no_quoting, "\"a\tb\"", vec![vec!["\"a", "b\""]],
+           |rdr: Csv<_>| rdr.tsv());
+
+parses_to!(raw_fields, "a,\"b,c\",\"d\"\"e\"", vec![vec!["a", "\"b,c\"", "\"d\"\"e\""]],
+           |rdr: Csv<_>| rdr.raw_fields(true));
+
+parses_to!(select_columns, "a,b,c\nd,\"e,f\",g", vec![vec!["c", "a"], vec!["g", "d"]],
+           |rdr: Csv<_>| rdr.select(vec![2, 0]));
+parses_to!(select_columns_repeated, "a,b,c", vec![vec!["b", "b"]],
+           |rdr: Csv<_>| rdr.select(vec![1, 1]));
+
 parses_to!(delimiter_tabs, "a\tb", vec![vec!["a", "b"]],
            |rdr: Csv<_>| rdr.delimiter(b'\t'));
+parses_to!(delimiter_tsv, "a\tb,c\nd\te", vec![vec!["a", "b,c"], vec!["d", "e"]],
+           |rdr: Csv<_>| rdr.tsv());
 parses_to!(delimiter_weird, "azb", vec![vec!["a", "b"]],
            |rdr: Csv<_>| rdr.delimiter(b'z'));
 
@@ -161,8 +213,12 @@ decodes_to!(decode_many_int, "1,2", (usize, i16), vec![(1usize, 2i16)]);
 decodes_to!(decode_float, "1,1.0,1.5",
             (f64, f64, f64), vec![(1f64, 1.0, 1.5)]);
 decodes_to!(decode_char, "a", (char), vec![('a')]);
+decodes_to!(decode_multibyte_char, "é", (char), vec![('é')]);
 decodes_to!(decode_str, "abc", (String,), vec![("abc".into(),)]);
 
+decodes_to!(decode_nil, "a,ignored,1", (String, (), usize),
+            vec![("a".into(), (), 1usize)]);
+
 decodes_to!(decode_opt_int, "a", (Option<usize>,), vec![(None,)]);
 decodes_to!(decode_opt_float, "a", (Option<f64>,), vec![(None,)]);
 decodes_to!(decode_opt_char, "ab", (Option<char>,), vec![(None,)]);
@@ -174,11 +230,50 @@ decodes_to!(decode_opt_val, "1.0", (Option<Val>,), vec![(None,)]);
 
 decodes_to!(decode_tail, "abc,1,2,3,4", (String, Vec<usize>),
             vec![("abc".into(), vec![1usize, 2, 3, 4])]);
+decodes_to!(decode_whole_row_vec, "1,2,3,4", Vec<f64>, vec![vec![1.0, 2.0, 3.0, 4.0]]);
+decodes_to!(decode_whole_row_vec_option, "1,,3", Vec<Option<u8>>, vec![vec![Some(1), None, Some(3)]]);
+decodes_to!(decode_empty_tail, "abc", (String, Vec<usize>), vec![("abc".into(), vec![])]);
+decodes_to!(decode_array, "1,2,3", [u8; 3], vec![[1u8, 2, 3]]);
+decodes_to!(decode_tail_array, "abc,1,2", (String, [u8; 2]), vec![("abc".into(), [1u8, 2])]);
+
+#[test]
+fn decode_array_wrong_length() {
+    let row = Csv::from_string("1,2").next().unwrap().unwrap();
+    match row.decode::<[u8; 3]>() {
+        Err(::error::Error::Decode(msg)) => assert_eq!(msg, "wrong array length, 2 columns left"),
+        r => panic!("expected a Decode error, got {:?}", r),
+    }
+    let row = Csv::from_string("1,2,3,4").next().unwrap().unwrap();
+    match row.decode::<[u8; 3]>() {
+        Err(::error::Error::Decode(msg)) => assert_eq!(msg, "wrong array length, 4 columns left"),
+        r => panic!("expected a Decode error, got {:?}", r),
+    }
+    // errors on the elements are left as they are
+    let row = Csv::from_string("a,bc").next().unwrap().unwrap();
+    match row.decode::<Vec<char>>() {
+        Err(::error::Error::Decode(msg)) => {
+            assert_eq!(msg, "Expected a single char in column 1, found 2 chars");
+        }
+        r => panic!("expected a Decode error, got {:?}", r),
+    }
+}
 
 #[derive(Debug, RustcDecodable, RustcEncodable, PartialEq, Eq)]
 enum MyEnum { Enum1, Enum2 }
 decodes_to!(decode_myenum, "Enum1,Enum1,Enum2", (MyEnum, MyEnum, MyEnum),
             vec![(MyEnum::Enum1, MyEnum::Enum1, MyEnum::Enum2)]);
+
+#[test]
+fn decode_myenum_lenient() {
+    let data = "enum1,1,ENUM2,0";
+    let row = Csv::from_string(data).next().unwrap().unwrap();
+    assert!(row.decode::<(MyEnum, MyEnum, MyEnum, MyEnum)>().is_err());
+    let row = CsvBuilder::new().lenient_enums(true).from_string(data).next().unwrap().unwrap();
+    assert_eq!(row.decode::<(MyEnum, MyEnum, MyEnum, MyEnum)>().unwrap(),
+               (MyEnum::Enum1, MyEnum::Enum2, MyEnum::Enum2, MyEnum::Enum1));
+    let row = CsvBuilder::new().lenient_enums(true).from_string("2").next().unwrap().unwrap();
+    assert!(row.decode::<MyEnum>().is_err());
+}
 #[test]
 fn no_headers_no_skip_one_record() {
     let mut d = Csv::from_string("a,b");
@@ -220,6 +315,19 @@ fn utf8_bom_quotes() {
     assert_eq!(c, vec![b"abc", b"xyz"]);
 }
 
+#[test]
+fn utf8_bom_headers() {
+    let mut d = Csv::from_reader(&b"\xef\xbb\xbfa,b\nc,d"[..]).has_header(true);
+    assert_eq!(d.headers(), vec!["a".to_string(), "b".to_string()]);
+}
+
+#[test]
+fn utf8_bom_kept() {
+    let mut d = Csv::from_reader(&b"\xef\xbb\xbfa,b"[..]).skip_bom(false);
+    let r = d.next().unwrap().unwrap();
+    let c = r.bytes_columns().collect::<Vec<_>>();
+    assert_eq!(c, vec![&b"\xef\xbb\xbfa"[..], &b"b"[..]]);
+}
 
 #[test]
 fn byte_strings_invalid_utf8() {
@@ -236,6 +344,14 @@ fn invalid_utf8() {
     let _ = d.next().unwrap().unwrap().columns().unwrap();
 }
 
+#[test]
+fn invalid_utf8_position() {
+    let mut d = Csv::from_reader(&b"a,b\nc,d\xffe"[..]);
+    d.next();
+    let err = d.next().unwrap().unwrap().decode::<(String, String)>().err().unwrap();
+    assert_eq!(err.to_string(), "stream did not contain valid UTF-8 at byte 3 of line 2");
+}
+
 #[test]
 fn seeking() {
     let data = "1,2\n3,4\n5,6\n";
@@ -254,3 +370,2070 @@ fn seeking() {
         assert_eq!(vals, vec!((1, 2), (3, 4), (5, 6)));
     }
 }
+
+#[test]
+fn string_records() {
+    let records = Csv::from_string("a,\"b,c\"\nd,e").records()
+        .collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].len(), 2);
+    assert_eq!(records[0].get(1), Some("b,c"));
+    assert_eq!(records[1].get(0), Some("d"));
+    assert_eq!(records[1].get(2), None);
+}
+
+#[test]
+fn writer_quoting() {
+    let mut w = CsvWriter::from_writer(Vec::new());
+    w.write_record(&["a", "b,c", "d\"e", "f\ng", ""]).unwrap();
+    assert_eq!(w.into_inner(), &b"a,\"b,c\",\"d\"\"e\",\"f\ng\",\n"[..]);
+}
+
+#[test]
+fn writer_round_trip() {
+    let data = "a,\"b,c\",\"d\"\"e\"\nx,y,\"z\nw\"\n";
+    let mut w = CsvWriter::from_writer(Vec::new());
+    for r in Csv::from_string(data) {
+        let r = r.unwrap();
+        w.write_record(r.columns().unwrap()).unwrap();
+    }
+    let written = String::from_utf8(w.into_inner()).unwrap();
+    let expected = Csv::from_string(data).records().map(|r| r.unwrap()).collect::<Vec<_>>();
+    let got = Csv::from_string(&written).records().map(|r| r.unwrap()).collect::<Vec<_>>();
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn writer_round_trip_file() {
+    let data = ::std::fs::read_to_string("./examples/data/simple.csv").unwrap();
+    let path = ::std::env::temp_dir().join("quick_csv_writer_round_trip.csv");
+    {
+        let mut w = CsvWriter::from_file(&path).unwrap();
+        for r in Csv::from_string(&data) {
+            w.write_record(r.unwrap().columns().unwrap()).unwrap();
+        }
+        w.flush().unwrap();
+    }
+    let expected = Csv::from_string(&data).records().map(|r| r.unwrap()).collect::<Vec<_>>();
+    let got = Csv::from_file(&path).unwrap().records().map(|r| r.unwrap()).collect::<Vec<_>>();
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn unexpected_quote_error() {
+    let mut d = Csv::from_string("a,b\"c");
+    match d.next() {
+        Some(Err(::error::Error::UnexpectedQuote { line, pos, line_number })) => {
+            assert_eq!(line, b"a,b\"c");
+            assert_eq!(pos, 3);
+            assert_eq!(line_number, 1);
+        },
+        _ => panic!("expected UnexpectedQuote error"),
+    }
+}
+
+#[test]
+fn misspelled_unexpected_quote_alias() {
+    // kept so that existing `match`es still compile, never produced
+    let e = ::error::Error::UnexpextedQuote;
+    assert_eq!(e.clone(), ::error::Error::UnexpextedQuote);
+    assert!(e != ::error::Error::EOL);
+    assert!(e.to_string().contains("quote"));
+}
+
+#[test]
+fn size_hint_bounds() {
+    let data = "a,b\nc,d\ne,f\n";
+    let mut d = Csv::from_string(data);
+    assert_eq!(d.size_hint(), (0, Some(data.len())));
+    assert_eq!(d.estimated_remaining(), None);
+    d.next();
+    assert_eq!(d.estimated_remaining(), Some(2));
+    let (low, high) = d.size_hint();
+    assert!(low <= 2 && high.unwrap() >= 2);
+    assert_eq!(d.by_ref().count(), 2);
+    assert_eq!(d.size_hint(), (0, Some(0)));
+}
+
+#[test]
+fn size_hint_skipped_bytes() {
+    let mut d = CsvBuilder::new().skip_empty_lines(true).from_string("a\n\n\n");
+    d.next();
+    assert_eq!(d.size_hint().0, 0);
+    assert_eq!(d.count(), 0);
+
+    let d = Csv::from_string("\u{feff}");
+    assert_eq!(d.size_hint().0, 0);
+    assert_eq!(d.count(), 0);
+}
+
+#[test]
+fn size_hint_unknown() {
+    let d = Csv::from_reader(&b"a,b"[..]);
+    assert_eq!(d.size_hint(), (0, None));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn size_hint_special_file() {
+    // `/proc` files have a zero length but are not empty
+    let d = Csv::from_file("/proc/self/status").unwrap().flexible(true);
+    assert_eq!(d.size_hint(), (0, None));
+    assert!(d.count() > 0);
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn gzip_file() {
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    let path = ::std::env::temp_dir().join("quick_csv_gzip_file.csv.gz");
+    {
+        let file = ::std::fs::File::create(&path).unwrap();
+        let mut e = GzEncoder::new(file, Compression::default());
+        e.write_all(b"a,b\nc,d\n").unwrap();
+        e.finish().unwrap();
+    }
+    let rows = Csv::from_gzip_file(&path).unwrap()
+        .map(|r| r.unwrap().decode::<(String, String)>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![("a".to_string(), "b".to_string()), ("c".to_string(), "d".to_string())]);
+}
+
+#[test]
+fn unescaped_quote_error() {
+    let mut d = Csv::from_string("x,y\na,\"b\"c,d\ne,f");
+    d.next();
+    match d.next() {
+        Some(Err(e @ ::error::Error::UnescapedQuote { .. })) => {
+            if let ::error::Error::UnescapedQuote { ref line, pos, line_number } = e {
+                assert_eq!(&line[..], &b"a,\"b\"c,d"[..]);
+                assert_eq!(pos, 4);
+                assert_eq!(line_number, 2);
+            }
+            assert!(e.to_string().contains("a,\"b\"c,d"));
+        },
+        _ => panic!("expected UnescapedQuote error"),
+    }
+}
+
+#[test]
+fn unescaped_quote_terminator_cr() {
+    // `\n` is an ordinary byte in `Cr` mode, it cannot follow a closing quote
+    let mut d = Csv::from_string("\"a\"\nb\rc\r").terminator(Terminator::Cr);
+    match d.next() {
+        Some(Err(::error::Error::UnescapedQuote { pos, line_number, .. })) => {
+            assert_eq!(pos, 2);
+            assert_eq!(line_number, 1);
+        }
+        r => panic!("expected UnescapedQuote error, got {:?}", r),
+    }
+    let mut d = Csv::from_string("\"a\"\rb\r").terminator(Terminator::Cr);
+    assert_eq!(d.next().unwrap().unwrap().decode::<(String,)>().unwrap().0, "a");
+}
+
+#[test]
+fn on_error_skip() {
+    let data = "a,b\nc,\"d\ne\"f,g\nh,i\nj\"k,l\nm\nn,o";
+    let mut d = Csv::from_string(data).on_error(OnError::Skip);
+    let rows = d.by_ref().map(|r| r.unwrap().decode::<Vec<String>>().unwrap())
+                .collect::<Vec<_>>();
+    assert_eq!(rows, vec![vec!["a", "b"], vec!["h", "i"], vec!["n", "o"]]);
+    let lines = d.errors().iter().map(|&(l, _)| l).collect::<Vec<_>>();
+    assert_eq!(lines, vec![2, 4, 5]);
+}
+
+#[test]
+fn on_error_skip_malformed_header() {
+    let mut d = CsvBuilder::new().has_header(true).on_error(OnError::Skip)
+        .from_string("a\"b,c\nx,y\nz,w");
+    match d.try_headers() {
+        Err(::error::Error::UnexpectedQuote { line_number, .. }) => assert_eq!(line_number, 1),
+        r => panic!("expected an UnexpectedQuote error, got {:?}", r),
+    }
+    let rows = d.by_ref().map(|r| r.unwrap().decode::<Vec<String>>().unwrap())
+                .collect::<Vec<_>>();
+    assert_eq!(rows, vec![vec!["x", "y"], vec!["z", "w"]]);
+    assert!(d.errors().is_empty());
+}
+
+#[test]
+fn on_error_stop() {
+    let d = Csv::from_string("a,b\nc\"d,e\nf,g");
+    let rows = d.collect::<Vec<_>>();
+    assert_eq!(rows.len(), 2);
+    assert!(rows[1].is_err());
+}
+
+#[test]
+fn on_error_skip_small_buffer() {
+    let data = &b"a,b\nc,\"d\"e,f\ng,h"[..];
+    let d = Csv::from_reader(io::BufReader::with_capacity(3, data)).on_error(OnError::Skip);
+    let rows = d.map(|r| r.unwrap().decode::<Vec<String>>().unwrap()).collect::<Vec<_>>();
+    assert_eq!(rows, vec![vec!["a", "b"], vec!["g", "h"]]);
+}
+
+#[test]
+fn on_error_skip_bytes_terminator() {
+    let data = &b"a\"b||c||d||"[..];
+    for &capacity in &[3, 4, 64] {
+        let d = CsvBuilder::new().terminator(Terminator::Bytes(b"||".to_vec()))
+            .on_error(OnError::Skip)
+            .build(io::BufReader::with_capacity(capacity, data));
+        let rows = d.map(|r| r.unwrap().decode::<Vec<String>>().unwrap()).collect::<Vec<_>>();
+        assert_eq!(rows, vec![vec!["c"], vec!["d"]], "capacity {}", capacity);
+    }
+}
+
+#[test]
+fn position() {
+    let data = "\u{feff}a,b\r\n\"c\nd\",e\nf\"g,h\ni,j";
+    let mut d = Csv::from_string(data).on_error(OnError::Skip);
+    assert_eq!(d.position(), Position { byte: 0, line: 0, record: 0 });
+    d.next();
+    assert_eq!(d.position(), Position { byte: 8, line: 1, record: 1 });
+    d.next();
+    assert_eq!(d.position(), Position { byte: 16, line: 2, record: 2 });
+    d.next();
+    assert_eq!(d.position(), Position { byte: data.len() as u64, line: 4, record: 3 });
+    assert!(d.next().is_none());
+}
+
+#[test]
+fn record_capacity() {
+    let d = Csv::from_string("a,b,c\nd,e,f").record_capacity(64, 8);
+    let rows = d.map(|r| r.unwrap().len()).collect::<Vec<_>>();
+    assert_eq!(rows, vec![3, 3]);
+}
+
+#[test]
+fn small_buffer_columns() {
+    let data = &b"abc,de,\"f,g\",hijkl\nm,\"no\",p,q\n"[..];
+    let d = Csv::from_reader(io::BufReader::with_capacity(4, data));
+    let rows = d.map(|r| r.unwrap().decode::<Vec<String>>().unwrap()).collect::<Vec<_>>();
+    assert_eq!(rows, vec![vec!["abc", "de", "f,g", "hijkl"], vec!["m", "no", "p", "q"]]);
+}
+
+#[test]
+fn tsv_file() {
+    let path = ::std::env::temp_dir().join("quick_csv_tsv_file.tsv");
+    ::std::fs::write(&path, "a\tb\nc,d\te\n").unwrap();
+    let rows = Csv::from_tsv_file(&path).unwrap()
+        .map(|r| r.unwrap().decode::<(String, String)>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![("a".to_string(), "b".to_string()), ("c,d".to_string(), "e".to_string())]);
+}
+
+#[test]
+fn extension_delimiter() {
+    let delimiter = |p: &str| CsvBuilder::new().extension_delimiter(p).from_string("").get_delimiter();
+    assert_eq!(delimiter("a.tsv"), b'\t');
+    assert_eq!(delimiter("dir/a.TAB"), b'\t');
+    assert_eq!(delimiter("a.scsv"), b';');
+    assert_eq!(delimiter("a.csv"), b',');
+    assert_eq!(delimiter("a.txt"), b',');
+    assert_eq!(delimiter("tsv"), b',');
+    let csv = CsvBuilder::new().extension_delimiter("a.tsv").delimiter(b'|').from_string("");
+    assert_eq!(csv.get_delimiter(), b'|');
+}
+
+#[test]
+fn from_path_auto() {
+    let path = ::std::env::temp_dir().join("quick_csv_from_path_auto.scsv");
+    ::std::fs::write(&path, "a;b\nc,d;e\n").unwrap();
+    let rows = Csv::from_path_auto(&path).unwrap()
+        .map(|r| r.unwrap().decode::<(String, String)>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![("a".to_string(), "b".to_string()), ("c,d".to_string(), "e".to_string())]);
+}
+
+#[test]
+fn read_128() {
+    let r = Csv::from_string("170141183460469231731687303715884105727,340282366920938463463374607431768211455,x")
+        .next().unwrap().unwrap();
+    let mut cols = r.columns().unwrap();
+    assert_eq!(cols.read_i128().unwrap(), i128::max_value());
+    assert_eq!(cols.read_u128().unwrap(), u128::max_value());
+    assert!(cols.read_i128().is_err());
+    assert!(cols.read_u128().is_err());
+}
+
+#[test]
+fn bool_tokens() {
+    let r = Csv::from_string("1,0,yes,no,true").bool_tokens(&["1", "yes"], &["0", "no"])
+        .next().unwrap().unwrap();
+    assert_eq!(r.decode::<(bool, bool, bool, bool, bool)>().unwrap(),
+               (true, false, true, false, true));
+}
+
+#[test]
+fn bool_tokens_strict() {
+    let r = Csv::from_string("1").next().unwrap().unwrap();
+    assert!(r.decode::<(bool,)>().is_err());
+}
+
+#[test]
+fn null_tokens() {
+    let r = Csv::from_string("NA,\\N,na,1").null_tokens(vec!["NA".to_string(), "\\N".to_string()])
+        .next().unwrap().unwrap();
+    let v = r.decode::<(Option<i32>, Option<i32>, Option<String>, Option<i32>)>().unwrap();
+    assert_eq!(v, (None, None, Some("na".to_string()), Some(1)));
+}
+
+#[test]
+fn null_tokens_ignore_case() {
+    let r = Csv::from_string("na,Null").null_tokens(vec!["NA".to_string(), "NULL".to_string()])
+        .null_ignore_case(true)
+        .next().unwrap().unwrap();
+    assert_eq!(r.decode::<(Option<i32>, Option<String>)>().unwrap(), (None, None));
+}
+
+#[test]
+fn try_headers_error() {
+    let mut d = Csv::from_reader(&b"a\xff,b\nc,d\ne,f"[..]).has_header(true);
+    assert!(d.try_headers().is_err());
+    assert_eq!(d.try_headers().unwrap(), Vec::<String>::new());
+    assert_eq!(d.headers(), Vec::<String>::new());
+    let rows = d.map(|r| r.unwrap().decode::<(String, String)>().unwrap()).collect::<Vec<_>>();
+    assert_eq!(rows.len(), 2);
+}
+
+#[test]
+fn try_headers_cached() {
+    let mut d = Csv::from_string("a,b\nc,d");
+    d = d.has_header(true);
+    assert_eq!(d.try_headers().unwrap(), vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(d.try_headers().unwrap(), vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(d.count(), 1);
+}
+
+#[test]
+fn set_headers() {
+    let mut d = Csv::from_string("a,b\nc,d").set_headers(vec!["x".to_string(), "y".to_string()]);
+    assert_eq!(d.headers(), vec!["x".to_string(), "y".to_string()]);
+    let r = d.next().unwrap().unwrap();
+    assert_eq!(r.decode::<(String, String)>().unwrap(), ("a".to_string(), "b".to_string()));
+    assert_eq!(d.count(), 1);
+}
+
+#[test]
+fn select_columns_out_of_range() {
+    let d = Csv::from_string("a,b\nc\nd,e").flexible(true).select(vec![1]);
+    let rows = d.map(|r| r.and_then(|r| r.decode::<(String,)>())).collect::<Vec<_>>();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].as_ref().unwrap().0, "b");
+    assert_eq!(rows[1], Err(::error::Error::ColumnMismatch { expected: 2, got: 1, line: 2 }));
+
+    let mut d = Csv::from_string("a,b\nc\nd,e\n").flexible(true).select(vec![1])
+        .on_error(OnError::Skip);
+    let rows = (&mut d).map(|r| r.unwrap().decode::<(String,)>().unwrap().0).collect::<Vec<_>>();
+    assert_eq!(rows, vec!["b", "e"]);
+    assert_eq!(d.errors(), &[(2, ::error::Error::ColumnMismatch { expected: 2, got: 1, line: 2 })]);
+    assert_eq!(d.current_line(), 3);
+}
+
+#[test]
+fn peek_record() {
+    let mut d = Csv::from_string("a,b\nc,d");
+    assert_eq!(d.peek_record().unwrap().unwrap().decode::<(String, String)>().unwrap(),
+               ("a".to_string(), "b".to_string()));
+    assert_eq!(d.peek_record().unwrap().unwrap().len(), 2);
+    assert_eq!(d.current_line(), 1);
+    assert_eq!(d.size_hint().0, 1);
+    let r = d.next().unwrap().unwrap();
+    assert_eq!(r.decode::<(String, String)>().unwrap(), ("a".to_string(), "b".to_string()));
+    assert_eq!(d.current_line(), 1);
+    assert!(d.peek_record().unwrap().is_ok());
+    assert_eq!(d.current_line(), 2);
+    assert!(d.next().is_some());
+    assert!(d.peek_record().is_none());
+    assert!(d.next().is_none());
+}
+
+#[test]
+fn fill_missing() {
+    let d = Csv::from_string("a\nx,y").flexible(true).fill_missing(true);
+    let rows = d.map(|r| r.unwrap().decode::<(String, Option<String>)>().unwrap())
+                .collect::<Vec<_>>();
+    assert_eq!(rows, vec![("a".to_string(), None), ("x".to_string(), Some("y".to_string()))]);
+}
+
+#[test]
+fn no_fill_missing() {
+    let mut d = Csv::from_string("a\nx,y").flexible(true);
+    assert!(d.next().unwrap().unwrap().decode::<(String, Option<String>)>().is_err());
+}
+
+#[test]
+fn unterminated_quote_error() {
+    let mut d = Csv::from_string("a,b\nc,\"d\ne,f");
+    d.next();
+    match d.next() {
+        Some(Err(::error::Error::Parse(msg))) => assert_eq!(msg,
+            "line 2: unterminated quoted field in column 1 starting at byte 2 of the record"),
+        _ => panic!("expected Parse error"),
+    }
+    assert!(d.next().is_none());
+}
+
+#[test]
+fn error_eq_clone() {
+    use error::Error;
+    let mut d = Csv::from_string("a,b\nc");
+    d.next();
+    let err = d.next().unwrap().err().unwrap();
+    assert_eq!(err, Error::ColumnMismatch { expected: 2, got: 1, line: 2 });
+    assert_eq!(err.to_string(), "Expecting 2 columns, found 1 at line 2");
+    assert_eq!(err.clone(), err);
+    assert!(err != Error::EOL);
+
+    let io = Error::Io(io::Error::new(io::ErrorKind::NotFound, "a"));
+    assert_eq!(io, Error::Io(io::Error::new(io::ErrorKind::NotFound, "b")));
+    assert!(io != Error::Io(io::Error::new(io::ErrorKind::Other, "a")));
+    assert_eq!(io.clone().to_string(), "a");
+}
+
+#[test]
+fn error_into_io_error() {
+    use error::Error;
+    let mismatch = Error::ColumnMismatch { expected: 2, got: 1, line: 3 };
+    let err: io::Error = mismatch.clone().into();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), mismatch.to_string());
+
+    let err: io::Error = Error::Io(io::Error::new(io::ErrorKind::NotFound, "missing")).into();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    assert_eq!(Error::from(err), Error::Io(io::Error::new(io::ErrorKind::NotFound, "missing")));
+}
+
+#[test]
+fn terminator_any_small_buffer() {
+    let data = &b"ab\r\ncd\ref\r\n"[..];
+    let d = Csv::from_reader(io::BufReader::with_capacity(3, data)).terminator(Terminator::Any);
+    let rows = d.map(|r| r.unwrap().decode::<(String,)>().unwrap().0).collect::<Vec<_>>();
+    assert_eq!(rows, vec!["ab", "cd", "ef"]);
+}
+
+#[test]
+fn row_as_bytes() {
+    let mut d = Csv::from_reader(&b"a,\"b,c\",d\r\ne\xff,f,g"[..]);
+    let r = d.next().unwrap().unwrap();
+    assert_eq!(r.as_bytes(), &b"a,\"b,c\",d"[..]);
+    assert_eq!(r.as_str().unwrap(), "a,\"b,c\",d");
+    let r = d.next().unwrap().unwrap();
+    assert_eq!(r.as_bytes(), &b"e\xff,f,g"[..]);
+    assert!(r.as_str().is_err());
+
+    // doubled quotes are unescaped, unless `raw_fields` is set
+    let data = "\"a\"\"b\",c";
+    let r = Csv::from_string(data).next().unwrap().unwrap();
+    assert_eq!(r.as_bytes(), &b"\"a\"b\",c"[..]);
+    let r = CsvBuilder::new().raw_fields(true).from_string(data).next().unwrap().unwrap();
+    assert_eq!(r.as_bytes(), data.as_bytes());
+}
+
+#[test]
+fn row_clone_debug() {
+    let mut d = Csv::from_string("a,\"b,c\"");
+    let r = d.next().unwrap().unwrap();
+    let c = r.clone();
+    assert_eq!(r.decode::<(String, String)>().unwrap(), c.decode::<(String, String)>().unwrap());
+    assert_eq!(format!("{:?}", c), "Row { line: 1, columns: [\"a\", \"b,c\"] }");
+
+    let r = Csv::from_reader(&b"a\xff"[..]).next().unwrap().unwrap();
+    assert_eq!(format!("{:?}", r), "Row { line: 1, columns: [[97, 255]] }");
+}
+
+#[test]
+fn row_eq() {
+    let rows = Csv::from_string("\"a\",b\na,b\na,\"b,c\"\na,b,\n\"a\"\"\",b")
+        .flexible(true).map(|r| r.unwrap()).collect::<Vec<_>>();
+    assert_eq!(rows[0], rows[1]);
+    assert!(rows[0] != rows[2]);
+    assert!(rows[1] != rows[3]);
+    assert!(rows[0] != rows[4]);
+}
+
+#[test]
+fn terminator_bytes_small_buffer() {
+    let data = &b"ab<=>c<=<=>de<=>"[..];
+    for cap in 1..6 {
+        let d = Csv::from_reader(io::BufReader::with_capacity(cap, data))
+            .terminator(Terminator::Bytes(b"<=>".to_vec()));
+        let rows = d.map(|r| r.unwrap().decode::<(String,)>().unwrap().0).collect::<Vec<_>>();
+        assert_eq!(rows, vec!["ab", "c<=", "de"], "capacity {}", cap);
+    }
+}
+
+#[test]
+fn terminator_quoted_end_small_buffer() {
+    for cap in 1..6 {
+        let data = &b"\"a\"\x1eb,c\x1e\"d\"\x1e"[..];
+        let d = Csv::from_reader(io::BufReader::with_capacity(cap, data)).flexible(true)
+            .terminator(Terminator::Byte(b'\x1e'));
+        let rows = d.map(|r| r.unwrap().decode::<Vec<String>>().unwrap()).collect::<Vec<_>>();
+        assert_eq!(rows, vec![vec!["a"], vec!["b", "c"], vec!["d"]], "capacity {}", cap);
+
+        let data = &b"x,\"a\"<=>b,\"c\"<=>"[..];
+        let d = Csv::from_reader(io::BufReader::with_capacity(cap, data))
+            .terminator(Terminator::Bytes(b"<=>".to_vec()));
+        let rows = d.map(|r| r.unwrap().decode::<Vec<String>>().unwrap()).collect::<Vec<_>>();
+        assert_eq!(rows, vec![vec!["x", "a"], vec!["b", "c"]], "capacity {}", cap);
+    }
+}
+
+#[test]
+fn terminator_bytes_after_quote_small_buffer() {
+    // the start of the sequence after a closing quote must go on to the whole sequence
+    let cases = [(&b"\"a\"|,a|||"[..], &b"|~"[..], 2), (&b"\"\r|a\n~|\"~,"[..], &b"~~|"[..], 7),
+                 (&b"\"a\"|"[..], &b"|~"[..], 2)];
+    for &(data, seq, quote) in &cases {
+        // the last capacity reads the whole input at once
+        for cap in (1..6).chain(Some(data.len())) {
+            let r = Csv::from_reader(io::BufReader::with_capacity(cap, data))
+                .terminator(Terminator::Bytes(seq.to_vec())).next();
+            match r {
+                Some(Err(::error::Error::UnescapedQuote { pos, .. })) => {
+                    assert_eq!(pos, quote, "capacity {}", cap);
+                }
+                r => panic!("expected UnescapedQuote error with capacity {}, got {:?}", cap, r),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_decode() {
+    use rayon::prelude::*;
+    let data = (0..1000).map(|i| format!("{},{}\n", i, i * 2)).collect::<String>();
+    let rows = Csv::from_string(&data).par_decode::<(usize, usize)>()
+        .collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(rows, (0..1000).map(|i| (i, i * 2)).collect::<Vec<_>>());
+
+    let err = Csv::from_string("1,2\na,3\n").par_decode::<(usize, usize)>()
+        .collect::<Vec<_>>();
+    assert!(err[0].is_ok());
+    assert!(err[1].is_err());
+}
+
+#[test]
+fn into_channel() {
+    let data = (0..100).map(|i| format!("{},a\n", i)).collect::<String>();
+    let (rx, handle) = Csv::from_reader(io::Cursor::new(data.into_bytes())).into_channel(4);
+    let records = rx.iter().map(|r| r.unwrap().into_vec()).collect::<Vec<_>>();
+    handle.join().unwrap();
+    assert_eq!(records.len(), 100);
+    assert_eq!(records[42], vec!["42", "a"]);
+}
+
+#[test]
+fn into_channel_error() {
+    let data = b"a,b\nc,\"d\"e\nf,g\n".to_vec();
+    let (rx, handle) = Csv::from_reader(io::Cursor::new(data)).into_channel(0);
+    let records = rx.iter().collect::<Vec<_>>();
+    handle.join().unwrap();
+    assert_eq!(records.len(), 2);
+    assert!(records[0].is_ok());
+    assert!(records[1].is_err());
+}
+
+#[test]
+fn into_channel_dropped_receiver() {
+    let data = (0..100).map(|i| format!("{}\n", i)).collect::<String>();
+    let (rx, handle) = Csv::from_reader(io::Cursor::new(data.into_bytes())).into_channel(1);
+    drop(rx);
+    handle.join().unwrap();
+}
+
+#[test]
+fn whitespace_delimited() {
+    let data = "a    b   c\n  d e  f  \n\"g  h\"  \"\"   i\n";
+    let rows = Csv::from_string(data).whitespace_delimited(true)
+        .map(|r| r.unwrap().columns().unwrap().map(|c| c.to_string()).collect())
+        .collect::<Vec<Vec<_>>>();
+    assert_svec_eq(rows, vec![vec!["a", "b", "c"], vec!["d", "e", "f"], vec!["g  h", "", "i"]]);
+}
+
+#[test]
+fn whitespace_delimited_column_count() {
+    let mut csv = Csv::from_string("a b\n c  d \ne\n").whitespace_delimited(true);
+    assert_eq!(csv.next().unwrap().unwrap().len(), 2);
+    assert_eq!(csv.next().unwrap().unwrap().len(), 2);
+    assert!(csv.next().unwrap().is_err());
+
+    let rows = Csv::from_string("a\tb\t\tc\n\t \t\n\t\t\n")
+        .whitespace_delimited(true)
+        .delimiter(b'\t')
+        .flexible(true)
+        .skip_empty_lines(true)
+        .map(|r| r.unwrap().len())
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![3, 1]);
+}
+
+#[test]
+fn decode_lossy() {
+    let mut csv = Csv::from_reader(&b"a\xffb,c\n\"d\xfe\",\"e\"\"\"\nf,g"[..]);
+    let row = csv.next().unwrap().unwrap();
+    assert!(row.decode::<(String, String)>().is_err());
+    assert_eq!(row.decode_lossy::<(String, String)>().unwrap(),
+               ("a\u{fffd}b".to_string(), "c".to_string()));
+    let row = csv.next().unwrap().unwrap();
+    assert_eq!(row.decode_lossy::<(String, String)>().unwrap(),
+               ("d\u{fffd}".to_string(), "e\"".to_string()));
+    let row = csv.next().unwrap().unwrap();
+    assert_eq!(row.decode_lossy::<(String, String)>().unwrap(),
+               ("f".to_string(), "g".to_string()));
+}
+
+#[test]
+fn getters() {
+    let csv = Csv::from_string("a;b\nc;d");
+    assert_eq!(csv.get_delimiter(), b',');
+    assert!(!csv.is_flexible());
+    assert!(!csv.has_header_flag());
+    assert!(csv.is_quoting());
+    assert_eq!(*csv.get_terminator(), Terminator::Crlf);
+    assert!(!csv.is_raw_fields());
+    assert!(!csv.is_skip_empty_lines());
+    assert_eq!(csv.get_on_error(), OnError::Stop);
+
+    let csv = csv.delimiter(b';').flexible(true).has_header(true).quoting(false)
+        .terminator(Terminator::Any).raw_fields(true).skip_empty_lines(true)
+        .on_error(OnError::Skip);
+    assert_eq!(csv.get_delimiter(), b';');
+    assert!(csv.is_flexible());
+    assert!(csv.has_header_flag());
+    assert!(!csv.is_quoting());
+    assert_eq!(*csv.get_terminator(), Terminator::Any);
+    assert!(csv.is_raw_fields());
+    assert!(csv.is_skip_empty_lines());
+    assert_eq!(csv.get_on_error(), OnError::Skip);
+}
+
+#[test]
+fn reset() {
+    let data = "h1,h2\n1,2\n3,4\n".as_bytes().to_vec();
+    let mut csv = Csv::from_reader(io::Cursor::new(data)).has_header(true);
+    let first = (&mut csv).map(|r| r.unwrap().decode::<(u8, u8)>().unwrap()).collect::<Vec<_>>();
+    assert!(csv.next().is_none());
+    let line = csv.current_line();
+
+    csv.reset().unwrap();
+    assert_eq!(csv.current_line(), 0);
+    assert_eq!(csv.headers(), vec!["h1", "h2"]);
+    assert_eq!(csv.current_line(), 1);
+    let second = (&mut csv).map(|r| r.unwrap().decode::<(u8, u8)>().unwrap()).collect::<Vec<_>>();
+    assert_eq!(first, vec![(1, 2), (3, 4)]);
+    assert_eq!(first, second);
+    assert_eq!(csv.current_line(), line);
+}
+
+#[test]
+fn reset_keeps_given_headers() {
+    let data = "h1,h2\n1,2\n".as_bytes().to_vec();
+    let mut csv = CsvBuilder::new().has_header(true)
+        .set_headers(vec!["x".to_string(), "y".to_string()])
+        .build(io::Cursor::new(data));
+    assert_eq!(csv.next().unwrap().unwrap().decode::<(u8, u8)>().unwrap(), (1, 2));
+    csv.reset().unwrap();
+    assert_eq!(csv.headers(), vec!["x", "y"]);
+    assert_eq!(csv.next().unwrap().unwrap().decode::<(u8, u8)>().unwrap(), (1, 2));
+    assert!(csv.next().is_none());
+}
+
+#[test]
+fn reset_after_error() {
+    let data = "a,b\nc\n".as_bytes().to_vec();
+    let mut csv = Csv::from_reader(io::Cursor::new(data));
+    assert!(csv.next().unwrap().is_ok());
+    assert!(csv.next().unwrap().is_err());
+    assert!(csv.next().is_none());
+    csv.reset().unwrap();
+    assert_eq!(csv.column_count(), None);
+    assert!(csv.next().unwrap().is_ok());
+}
+
+#[test]
+fn count_records() {
+    let data = "a,b\n\"c\nd\",e\n\nf,g";
+    assert_eq!(Csv::from_string(data).count_records().unwrap(), 4);
+    assert_eq!(Csv::from_string(data).skip_empty_lines(true).count_records().unwrap(), 3);
+    assert_eq!(Csv::from_string(data).quoting(false).count_records().unwrap(), 5);
+    assert_eq!(Csv::from_string(data).has_header(true).count_records().unwrap(), 3);
+    assert_eq!(Csv::from_string("").count_records().unwrap(), 0);
+    assert_eq!(Csv::from_string("a\r\nb\r\n").count_records().unwrap(), 2);
+    // a line of a single `\r` is an empty record in `Crlf` mode
+    assert_eq!(Csv::from_string("\r").count_records().unwrap(), 1);
+    assert_eq!(Csv::from_string("|\n\r").count_records().unwrap(), 2);
+    assert_eq!(Csv::from_string("|\n\r").skip_empty_lines(true).count_records().unwrap(), 1);
+    assert_eq!(Csv::from_string("\r\r\n").skip_empty_lines(true).count_records().unwrap(), 1);
+    assert_eq!(Csv::from_string("a\r\nb\rc\nd").terminator(Terminator::Any)
+               .count_records().unwrap(), 4);
+    assert_eq!(Csv::from_string("a\x1eb||c||").terminator(Terminator::Bytes(b"||".to_vec()))
+               .count_records().unwrap(), 2);
+
+    let mut csv = Csv::from_string(data);
+    csv.next();
+    csv.peek_record();
+    assert_eq!(csv.count_records().unwrap(), 3);
+    assert!(csv.next().is_none());
+}
+
+#[test]
+fn count_records_matches_iterator() {
+    for data in &["a,\"b\r\n\"\"\"\r\n\r\n\"\",d\r\ne\n", "\r", "|\n\r", "\r\r\n\r\n"] {
+        for &skip in &[false, true] {
+            let count = Csv::from_string(data).flexible(true).skip_empty_lines(skip).count();
+            let fast = Csv::from_string(data).skip_empty_lines(skip).count_records().unwrap();
+            assert_eq!(count, fast, "{:?}", data);
+        }
+    }
+}
+
+#[test]
+fn overlapping_terminator_bytes() {
+    // `~~|` starts at the second `~` of `~~~|`
+    let data = "x~~~|y~~|z~~|";
+    let csv = || Csv::from_string(data).terminator(Terminator::Bytes(b"~~|".to_vec()));
+    let rows = csv().map(|r| r.unwrap().decode::<(String,)>().unwrap().0).collect::<Vec<_>>();
+    assert_eq!(rows, vec!["x~", "y", "z"]);
+    assert_eq!(csv().count_records().unwrap(), 3);
+    assert_eq!(csv().nth(1).unwrap().unwrap().decode::<(String,)>().unwrap().0, "y");
+
+    // resynchronizing after a malformed row
+    let data = "x\"~~~|y~~|z~~|";
+    let rows = Csv::from_string(data).terminator(Terminator::Bytes(b"~~|".to_vec()))
+        .on_error(OnError::Skip)
+        .map(|r| r.unwrap().decode::<(String,)>().unwrap().0).collect::<Vec<_>>();
+    assert_eq!(rows, vec!["y", "z"]);
+}
+
+#[test]
+fn overlapping_terminator_bytes_small_buffer() {
+    let seq = || Terminator::Bytes(b"~~|".to_vec());
+    for cap in 1..6 {
+        let data = &b"~~~|\n"[..];
+        let rows = Csv::from_reader(io::BufReader::with_capacity(cap, data)).terminator(seq())
+            .map(|r| r.unwrap().decode::<(String,)>().unwrap().0).collect::<Vec<_>>();
+        assert_eq!(rows, vec!["~", "\n"], "capacity {}", cap);
+
+        let data = &b"a,b~~~|c,\"d\""[..];
+        let rows = Csv::from_reader(io::BufReader::with_capacity(cap, data)).terminator(seq())
+            .map(|r| r.unwrap().decode::<Vec<String>>().unwrap()).collect::<Vec<_>>();
+        assert_eq!(rows, vec![vec!["a", "b~"], vec!["c", "d"]], "capacity {}", cap);
+    }
+}
+
+#[test]
+fn skip_rows() {
+    let data = "h1,h2\n1,2\n3,4\n5,6\n";
+    let mut csv = CsvBuilder::new().has_header(true).skip_rows(2).from_string(data);
+    assert_eq!(csv.headers(), vec!["h1", "h2"]);
+    assert_eq!(csv.current_line(), 3);
+    let rows = (&mut csv).map(|r| r.unwrap().decode::<(u8, u8)>().unwrap()).collect::<Vec<_>>();
+    assert_eq!(rows, vec![(5, 6)]);
+
+    let rows = CsvBuilder::new().skip_rows(1).from_string(data).count();
+    assert_eq!(rows, 3);
+    let rows = CsvBuilder::new().has_header(true).skip_rows(10).from_string(data).count();
+    assert_eq!(rows, 0);
+
+    let data = "h1,h2\n1,2\n3,4\n".as_bytes().to_vec();
+    let mut csv = CsvBuilder::new().has_header(true).skip_rows(1).build(io::Cursor::new(data));
+    assert_eq!(csv.next().unwrap().unwrap().decode::<(u8, u8)>().unwrap(), (3, 4));
+    csv.reset().unwrap();
+    assert_eq!(csv.next().unwrap().unwrap().decode::<(u8, u8)>().unwrap(), (3, 4));
+    assert!(csv.next().is_none());
+}
+
+#[test]
+fn skip_rows_error() {
+    let mut csv = CsvBuilder::new().has_header(true).skip_rows(2).from_string("h\n1\n2,3\n4\n");
+    assert!(csv.next().unwrap().is_err());
+    assert!(csv.next().is_none());
+}
+
+#[test]
+fn quote_errors_position() {
+    let mut d = Csv::from_string("a\n  \"a\"  ");
+    d.next();
+    let e = d.next().unwrap().unwrap_err();
+    assert_eq!(e, ::error::Error::UnexpectedQuote {
+        line: b"  \"a\"  ".to_vec(),
+        pos: 2,
+        line_number: 2,
+    });
+    assert!(e.to_string().contains("line 2, byte 2"));
+
+    let mut d = Csv::from_string("\"a\"b");
+    let e = d.next().unwrap().unwrap_err();
+    assert_eq!(e, ::error::Error::UnescapedQuote {
+        line: b"\"a\"b".to_vec(),
+        pos: 2,
+        line_number: 1,
+    });
+    assert!(e.to_string().contains("line 1, byte 2"));
+}
+
+#[test]
+fn into_owned_rows() {
+    let rows = Csv::from_reader(&b"a,\"b,c\"\nd\xff,e\nf,g"[..]).into_owned_rows().collect::<Vec<_>>();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0].as_ref().unwrap(), &vec!["a".to_string(), "b,c".to_string()]);
+    assert!(rows[1].is_err());
+    assert_eq!(rows[2].as_ref().unwrap(), &vec!["f".to_string(), "g".to_string()]);
+}
+
+#[test]
+fn decimal_separator() {
+    let mut csv = Csv::from_string("1.234,56;-0,5;1.000.000;3\n1,2,3;0;0;0")
+        .delimiter(b';')
+        .decimal_separator(b',')
+        .thousands_separator(b'.');
+    let row = csv.next().unwrap().unwrap();
+    assert_eq!(row.decode::<(f64, f32, u32, i8)>().unwrap(), (1234.56, -0.5, 1000000, 3));
+    assert!(csv.next().unwrap().unwrap().decode::<(f64, f32, u32, i8)>().is_err());
+
+    let row = Csv::from_string("1,234.5,\"1,000\"").thousands_separator(b',')
+        .next().unwrap().unwrap();
+    assert_eq!(row.decode::<(f64, f64, u16)>().unwrap(), (1.0, 234.5, 1000));
+
+    let row = Csv::from_string("1.5,2").next().unwrap().unwrap();
+    assert_eq!(row.decode::<(f64, u8)>().unwrap(), (1.5, 2));
+}
+
+#[test]
+fn row_into_iter() {
+    let row = Csv::from_reader(&b"a,\"b,c\",\xff"[..]).next().unwrap().unwrap();
+    let mut fields = Vec::new();
+    for field in &row {
+        fields.push(field);
+    }
+    assert_eq!(fields, vec![&b"a"[..], &b"b,c"[..], &b"\xff"[..]]);
+    assert_eq!(row.iter().collect::<Vec<_>>(), fields);
+}
+
+#[test]
+fn integer_radix() {
+    let row = Csv::from_string("0x10,0o17,0b101,-0x1F,ff").integer_radix(16)
+        .next().unwrap().unwrap();
+    assert_eq!(row.decode::<(u8, u16, u32, i64, u64)>().unwrap(), (16, 15, 5, -31, 255));
+
+    let row = Csv::from_string("0x10,10").integer_radix(10).next().unwrap().unwrap();
+    assert_eq!(row.decode::<(usize, u8)>().unwrap(), (16, 10));
+    assert!(row.decode::<(usize, f64)>().is_ok());
+
+    let row = Csv::from_string("0x10").next().unwrap().unwrap();
+    assert!(row.decode::<(u8,)>().is_err());
+}
+
+#[test]
+fn read_byte_record() {
+    let mut csv = Csv::from_reader(&b"a,\"b,\"\"c\"\n\xff,d\ne,f"[..]);
+    let mut record = ByteRecord::new();
+    assert!(csv.read_byte_record(&mut record).unwrap());
+    assert_eq!(record.len(), 2);
+    assert_eq!(record.get(0), Some(&b"a"[..]));
+    assert_eq!(record.get(1), Some(&b"b,\"c"[..]));
+    assert_eq!(record.get(2), None);
+    assert!(csv.read_byte_record(&mut record).unwrap());
+    assert_eq!(record.iter().collect::<Vec<_>>(), vec![&b"\xff"[..], &b"d"[..]]);
+    csv.peek_record();
+    assert!(csv.read_byte_record(&mut record).unwrap());
+    assert_eq!((&record).into_iter().collect::<Vec<_>>(), vec![&b"e"[..], &b"f"[..]]);
+    assert!(!csv.read_byte_record(&mut record).unwrap());
+    assert_eq!(csv.current_line(), 3);
+}
+
+#[test]
+fn max_record_size() {
+    let data = format!("a,b\n\"c,{}\n", "d\n".repeat(1000));
+    let mut csv = Csv::from_reader(io::BufReader::with_capacity(16, data.as_bytes()))
+        .max_record_size(64);
+    assert!(csv.next().unwrap().is_ok());
+    assert_eq!(csv.next().unwrap().unwrap_err(), ::error::Error::RecordTooLarge(64));
+    assert!(csv.next().is_none());
+
+    let rows = Csv::from_string("abcd,efgh\nij\n").flexible(true).max_record_size(9).count();
+    assert_eq!(rows, 2);
+}
+
+#[test]
+fn map_fields() {
+    let rows = Csv::from_reader(&b"a,\"b,c\"\n\xff,$12\n"[..])
+        .map_fields(|f| f.to_uppercase().into())
+        .map(|r| r.unwrap().bytes_columns().map(|c| c.to_vec()).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![vec![b"A".to_vec(), b"B,C".to_vec()],
+                          vec![b"\xff".to_vec(), b"$12".to_vec()]]);
+
+    let row = Csv::from_string("\"$12\",13")
+        .map_fields(|f| ::std::borrow::Cow::Borrowed(f.trim_start_matches('$')))
+        .next().unwrap().unwrap();
+    assert_eq!(row.decode::<(u8, u8)>().unwrap(), (12, 13));
+
+    // the header row is not mapped, even if quoted
+    let mut csv = CsvBuilder::new().has_header(true).map_fields(|f| f.to_uppercase().into())
+        .from_string("name,\"a,ge\"\nbob,x\n");
+    let rows = csv.by_ref().map(|r| r.unwrap().decode::<Vec<String>>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![vec!["BOB", "X"]]);
+    assert_eq!(csv.headers(), vec!["name", "a,ge"]);
+}
+
+#[test]
+fn parse_field_error() {
+    use error::{Error, FieldErrorKind};
+    let row = Csv::from_string("1,x,300,1.5,maybe").next().unwrap().unwrap();
+    match row.decode::<(u8, u8)>().unwrap_err() {
+        Error::ParseField { column, value, kind: FieldErrorKind::Int(_) } => {
+            assert_eq!(column, 1);
+            assert_eq!(value, "x");
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+    match row.decode::<(u8, String, u8)>().unwrap_err() {
+        Error::ParseField { column, kind: FieldErrorKind::Int(_), .. } => assert_eq!(column, 2),
+        e => panic!("unexpected error {:?}", e),
+    }
+    match row.decode::<(f64, f64)>().unwrap_err() {
+        Error::ParseField { column, kind: FieldErrorKind::Float(_), .. } => assert_eq!(column, 1),
+        e => panic!("unexpected error {:?}", e),
+    }
+    match row.decode::<((), (), (), f32, bool)>().unwrap_err() {
+        e @ Error::ParseField { column: 4, kind: FieldErrorKind::Bool(_), .. } => {
+            assert!(e.to_string().contains("column 4 ('maybe')"));
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[test]
+fn decode_error_column_index() {
+    use error::Error;
+    let row = Csv::from_string("a,b,c,d,e").next().unwrap().unwrap();
+    match row.decode::<(String, String, u8, String, String)>().unwrap_err() {
+        Error::ParseField { column, .. } => assert_eq!(column, 2),
+        e => panic!("unexpected error {:?}", e),
+    }
+    let row = Csv::from_string("a,b,cd").next().unwrap().unwrap();
+    let e = row.decode::<(char, char, char)>().unwrap_err();
+    assert!(e.to_string().contains("column 2"), "{}", e);
+}
+
+#[test]
+fn row_conversions() {
+    use std::convert::TryInto;
+    let mut csv = Csv::from_reader(&b"a,\"b,c\"\n\xff,d"[..]);
+    let row = csv.next().unwrap().unwrap();
+    let fields: Vec<String> = (&row).try_into().unwrap();
+    assert_eq!(fields, vec!["a", "b,c"]);
+    let bytes: Vec<Vec<u8>> = (&row).into();
+    assert_eq!(bytes, vec![b"a".to_vec(), b"b,c".to_vec()]);
+
+    let row = csv.next().unwrap().unwrap();
+    let fields: Result<Vec<String>, _> = (&row).try_into();
+    assert!(fields.is_err());
+    assert_eq!(Vec::<Vec<u8>>::from(&row), vec![b"\xff".to_vec(), b"d".to_vec()]);
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn to_json_lines() {
+    let mut out = Vec::new();
+    Csv::from_string("name,quote\nbob,\"say \"\"hi\"\"\"\nann,x,extra\n")
+        .has_header(true)
+        .flexible(true)
+        .to_json_lines(&mut out)
+        .unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(),
+               "{\"name\":\"bob\",\"quote\":\"say \\\"hi\\\"\"}\n\
+                {\"name\":\"ann\",\"quote\":\"x\",\"2\":\"extra\"}\n");
+
+    let mut out = Vec::new();
+    Csv::from_string("a,b\n").to_json_lines(&mut out).unwrap();
+    assert_eq!(out, b"{\"0\":\"a\",\"1\":\"b\"}\n");
+}
+
+#[test]
+fn columns_with_quoting() {
+    let row = Csv::from_string("\"a\",b,\"c\"\"\",\"\"").next().unwrap().unwrap();
+    let cols = row.columns_with_quoting().unwrap().collect::<Vec<_>>();
+    assert_eq!(cols, vec![("a", true), ("b", false), ("c\"", true), ("", true)]);
+
+    let row = Csv::from_string("\"a\",b").raw_fields(true).next().unwrap().unwrap();
+    let cols = row.columns_with_quoting().unwrap().collect::<Vec<_>>();
+    assert_eq!(cols, vec![("\"a\"", false), ("b", false)]);
+}
+
+#[test]
+fn quoting_failures() {
+    use error::Error;
+    fn first_error(data: &str) -> Error {
+        Csv::from_string(data).filter_map(|r| r.err()).next().expect("no error")
+    }
+    match first_error("x,\"ab\"cd\n") {
+        Error::UnescapedQuote { pos: 5, line_number: 1, .. } => (),
+        e => panic!("unexpected error {:?}", e),
+    }
+    match first_error("x,ab\"cd\n") {
+        Error::UnexpectedQuote { pos: 4, line_number: 1, .. } => (),
+        e => panic!("unexpected error {:?}", e),
+    }
+    let mut csv = Csv::from_reader(io::BufReader::with_capacity(2, &b"x,\"ab\ncd"[..]));
+    match csv.next().unwrap().unwrap_err() {
+        Error::Parse(msg) => assert!(msg.contains("unterminated quoted field"), "{}", msg),
+        e => panic!("unexpected error {:?}", e),
+    }
+    let rows = Csv::from_reader(io::BufReader::with_capacity(2, &b"x,\"ab\ncd\"\n"[..]))
+        .map(|r| r.unwrap().decode::<(String, String)>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![("x".to_string(), "ab\ncd".to_string())]);
+}
+
+#[test]
+fn infer_schema() {
+    use schema::{ColumnType, FieldType};
+    let data = "id,score,ok,name,note\n1,2,true,a,\n2,2.5,false,b,\n3,,true,4\n".as_bytes().to_vec();
+    let mut csv = Csv::from_reader(io::Cursor::new(data)).has_header(true).flexible(true);
+    let schema = csv.infer_schema(10).unwrap();
+    let t = |field_type, nullable| ColumnType { field_type: field_type, nullable: nullable };
+    assert_eq!(schema, vec![t(FieldType::Integer, false),
+                            t(FieldType::Float, true),
+                            t(FieldType::Bool, false),
+                            t(FieldType::String, false),
+                            t(FieldType::Unknown, true)]);
+    assert_eq!(csv.headers(), vec!["id", "score", "ok", "name", "note"]);
+    assert_eq!(csv.count(), 3);
+
+    let data = "1\n2\nx\n".as_bytes().to_vec();
+    let mut csv = Csv::from_reader(io::Cursor::new(data));
+    assert_eq!(csv.infer_schema(2).unwrap(), vec![t(FieldType::Integer, false)]);
+    assert_eq!(csv.infer_schema(3).unwrap(), vec![t(FieldType::String, false)]);
+}
+
+#[test]
+fn header_index() {
+    let csv = Csv::from_string("id,name,id\n1,a,2\n");
+    assert_eq!(csv.header_names(), None);
+    assert_eq!(csv.header_index("id"), None);
+    let mut csv = csv.has_header(true);
+    assert_eq!(csv.header_names(), None);
+    csv.headers();
+    assert_eq!(csv.header_names(), Some(&["id".to_string(), "name".to_string(), "id".to_string()][..]));
+    assert_eq!(csv.header_index("name"), Some(1));
+    assert_eq!(csv.header_index("id"), Some(0));
+    assert_eq!(csv.header_index("missing"), None);
+
+    let csv = csv.set_headers(vec!["x".to_string(), "name".to_string()]);
+    assert_eq!(csv.header_index("x"), Some(0));
+    assert_eq!(csv.header_index("id"), None);
+}
+
+
+/// Reader whose `fill_buf` alternately returns 1 and 2 bytes
+struct TinyReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    odd: bool,
+}
+
+impl<'a> TinyReader<'a> {
+    fn new(data: &'a str) -> TinyReader<'a> {
+        TinyReader { data: data.as_bytes(), pos: 0, odd: false }
+    }
+}
+
+impl<'a> Read for TinyReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let available = try!(io::BufRead::fill_buf(self));
+            let n = ::std::cmp::min(buf.len(), available.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        io::BufRead::consume(self, n);
+        Ok(n)
+    }
+}
+
+impl<'a> io::BufRead for TinyReader<'a> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let len = if self.odd { 2 } else { 1 };
+        let end = ::std::cmp::min(self.data.len(), self.pos + len);
+        Ok(&self.data[self.pos..end])
+    }
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt;
+        self.odd = !self.odd;
+    }
+}
+
+#[test]
+fn final_record_tiny_buffers() {
+    let inputs = ["a,b\nc,dd", "a,b\nc,dd\n", "a,b\nc,\"dd\"", "a,b\nc,\"dd\"\n",
+                  "a,\"b\nc\"", "a,\"b,c\"\r\n", "abc,\"defgh ijk\"", "a,\"\""];
+    for data in inputs.iter() {
+        let expected = Csv::from_string(data).flexible(true).into_owned_rows()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>();
+        let got = Csv::from_reader(TinyReader::new(data)).flexible(true).into_owned_rows()
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(got, expected, "{:?}", data);
+        let last = expected.last().unwrap().last().unwrap().clone();
+        assert!(!last.is_empty() || data.ends_with("\"\""), "{:?}", data);
+    }
+    let rows = Csv::from_reader(TinyReader::new("x,\"long quoted field\"")).into_owned_rows()
+        .map(|r| r.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![vec!["x".to_string(), "long quoted field".to_string()]]);
+}
+
+#[test]
+fn validate_field() {
+    use error::Error;
+    fn iso_date(i: usize, s: &str) -> ::error::Result<()> {
+        let ok = i != 2 || {
+            let parts = s.split('-').collect::<Vec<_>>();
+            parts.len() == 3 && parts[0].len() == 4
+                && parts.iter().all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+        };
+        if ok { Ok(()) } else { Err(Error::Decode(format!("'{}' is not a date", s))) }
+    }
+
+    let data = "a,1,2020-01-31\nb,2,31/01/2020\nc,3,\"2020-02-01\"";
+    let mut csv = Csv::from_string(data).validate_field(iso_date);
+    assert!(csv.next().unwrap().is_ok());
+    let e = csv.next().unwrap().unwrap_err();
+    assert_eq!(e, Error::InvalidField {
+        line: 2,
+        column: 2,
+        error: Box::new(Error::Decode("'31/01/2020' is not a date".to_string())),
+    });
+    assert!(csv.next().is_none());
+
+    let mut csv = Csv::from_string(data).validate_field(iso_date).on_error(OnError::Skip);
+    assert_eq!(csv.by_ref().count(), 2);
+    assert_eq!(csv.errors().len(), 1);
+    assert_eq!(csv.errors()[0].0, 2);
+    assert_eq!(csv.current_line(), 3);
+
+    // the header row is not validated
+    let number = |i: usize, s: &str| {
+        if i != 1 || s.parse::<u32>().is_ok() { Ok(()) } else { Err(Error::Decode(s.to_string())) }
+    };
+    let mut csv = CsvBuilder::new().has_header(true).validate_field(number)
+        .from_string("name,age\nbob,3\nal,4\n");
+    let rows = csv.by_ref().map(|r| r.unwrap().decode::<(String, u32)>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![("bob".to_string(), 3), ("al".to_string(), 4)]);
+    assert_eq!(csv.headers(), vec!["name", "age"]);
+}
+
+#[test]
+fn skip_rows_unterminated_quote() {
+    let data = "h\n\"x\ny\n1\n";
+    let mut csv = CsvBuilder::new().has_header(true).skip_rows(2).from_string(data);
+    match csv.next() {
+        Some(Err(::error::Error::Parse(msg))) => {
+            assert_eq!(msg, "line 2: unterminated quoted field starting at byte 0 of the record");
+        }
+        r => panic!("expected a Parse error, got {:?}", r),
+    }
+    assert!(csv.next().is_none());
+    assert!(Csv::from_string(data).nth(2).unwrap().is_err());
+    assert!(Csv::from_string(data).count_records().is_err());
+    assert!(CsvBuilder::new().has_header(true).skip_rows(2).from_string("h\n\"x\"\ny\n1\n")
+            .next().unwrap().is_ok());
+}
+
+#[test]
+fn nth_skips_rows() {
+    let data = "a,b\n\"c\nd\",e\n\nf,g\r\nh,i";
+    let mut csv = Csv::from_string(data);
+    let row = csv.nth(1).unwrap().unwrap();
+    assert_eq!(row.decode::<(String, String)>().unwrap(), ("c\nd".to_string(), "e".to_string()));
+    assert_eq!(csv.current_line(), 2);
+    let row = Csv::from_string(data).flexible(true).nth(2).unwrap().unwrap();
+    assert_eq!(row.len(), 1);
+    let mut csv = Csv::from_string(data).skip_empty_lines(true);
+    let row = csv.nth(2).unwrap().unwrap();
+    assert_eq!(row.decode::<(String, String)>().unwrap(), ("f".to_string(), "g".to_string()));
+    assert_eq!(csv.position().byte, data.len() as u64 - 3);
+    assert!(Csv::from_string(data).nth(5).is_none());
+
+    let mut csv = Csv::from_string("a\r\nb\rc").terminator(Terminator::Any);
+    assert_eq!(csv.nth(1).unwrap().unwrap().decode::<(String,)>().unwrap().0, "b");
+    assert_eq!(csv.next().unwrap().unwrap().decode::<(String,)>().unwrap().0, "c");
+
+    let mut csv = Csv::from_string(data);
+    csv.peek_record();
+    let row = csv.nth(1).unwrap().unwrap();
+    assert_eq!(row.decode::<(String, String)>().unwrap(), ("c\nd".to_string(), "e".to_string()));
+}
+
+#[test]
+fn float_tokens() {
+    let mut csv = Csv::from_string("Inf,-Infinity,n/a,1.5\n#N/A,INF,-inf,#N/A")
+        .float_tokens(&["Inf"], &["-Infinity"], &["N/A"])
+        .null_tokens(vec!["#N/A".to_string()]);
+    let (a, b, c, d) = csv.next().unwrap().unwrap().decode::<(f64, f32, f64, f64)>().unwrap();
+    assert_eq!(a, ::std::f64::INFINITY);
+    assert_eq!(b, ::std::f32::NEG_INFINITY);
+    assert!(c.is_nan());
+    assert_eq!(d, 1.5);
+    let row = csv.next().unwrap().unwrap();
+    assert_eq!(row.decode::<(Option<f64>, f64, f64, Option<f32>)>().unwrap(),
+               (None, ::std::f64::INFINITY, ::std::f64::NEG_INFINITY, None));
+    assert!(row.decode::<(f64,)>().is_err());
+}
+
+#[test]
+fn test_builder() {
+    let data = "h1;h2\n\n1;\"a;b\"\n2;c";
+    let mut csv = CsvBuilder::new()
+        .delimiter(b';')
+        .skip_empty_lines(true)
+        .has_header(true)
+        .from_string(data);
+    csv.headers();
+    assert_eq!(csv.header_names(), Some(&["h1".to_string(), "h2".to_string()][..]));
+    assert_eq!(csv.get_delimiter(), b';');
+    let rows = csv.map(|r| r.unwrap().decode::<(u8, String)>().unwrap()).collect::<Vec<_>>();
+    assert_eq!(rows, vec![(1, "a;b".to_string()), (2, "c".to_string())]);
+}
+
+#[test]
+fn test_builder_matches_deprecated_setters() {
+    let data = "a,b\n1,2,3\nno,yes";
+    let mut old = Csv::from_string(data).flexible(true).bool_tokens(&["yes"], &["no"])
+        .has_header(true);
+    let mut new = CsvBuilder::new().flexible(true).bool_tokens(&["yes"], &["no"])
+        .has_header(true).build(data.as_bytes());
+    assert_eq!(old.headers(), new.headers());
+    assert_eq!(old.next().unwrap().unwrap().len(), new.next().unwrap().unwrap().len());
+    assert_eq!(old.next().unwrap().unwrap().decode::<(bool, bool)>().unwrap(),
+               new.next().unwrap().unwrap().decode::<(bool, bool)>().unwrap());
+}
+
+#[test]
+fn has_header_is_lazy() {
+    let mut csv = CsvBuilder::new().has_header(true).from_string("a,b\n1,2\n3,4");
+    assert_eq!(csv.current_line(), 0);
+    assert_eq!(csv.header_names(), None);
+    assert_eq!(csv.next().unwrap().unwrap().decode::<(u8, u8)>().unwrap(), (1, 2));
+    assert_eq!(csv.headers(), vec!["a", "b"]);
+
+    // the header is never counted as a record
+    let mut csv = CsvBuilder::new().has_header(true).from_string("a,b\n1,2\n3,4");
+    assert_eq!(csv.count_records().unwrap(), 2);
+    let mut csv = CsvBuilder::new().has_header(true).from_string("a,b\n1,2\n3,4");
+    assert_eq!(csv.nth(1).unwrap().unwrap().decode::<(u8, u8)>().unwrap(), (3, 4));
+
+    // set headers are kept, the header row is still consumed
+    let mut csv = CsvBuilder::new().has_header(true).set_headers(vec!["x".to_string()])
+        .from_string("a,b\n1,2");
+    assert_eq!(csv.next().unwrap().unwrap().decode::<(u8, u8)>().unwrap(), (1, 2));
+    assert_eq!(csv.headers(), vec!["x"]);
+}
+
+#[test]
+fn records_with_position() {
+    let data = "h1,h2\n\n1,2\n\n\n3\n4,5\n";
+    let csv = CsvBuilder::new().has_header(true).skip_empty_lines(true).on_error(OnError::Skip)
+        .from_string(data);
+    let rows = csv.records_with_position()
+        .map(|(p, r)| (p, r.map(|r| r.len()).ok()))
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![
+        (Position { byte: 7, line: 1, record: 1 }, Some(2)),
+        (Position { byte: 15, line: 3, record: 2 }, Some(2)),
+    ]);
+    assert_eq!(&data[7..10], "1,2");
+    assert_eq!(&data[15..18], "4,5");
+
+    let csv = Csv::from_string("1,2\n3\n");
+    let rows = csv.records_with_position().map(|(p, r)| (p.byte, r.is_ok())).collect::<Vec<_>>();
+    assert_eq!(rows, vec![(0, true), (4, false)]);
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn columns_encoded() {
+    use encoding::Encoding;
+    use std::borrow::Cow;
+
+    let data: &[u8] = b"caf\xe9,\"\x80 5\",plain\n";
+    let row = Csv::from_reader(data).next().unwrap().unwrap();
+    assert!(row.columns().is_err());
+    let cols = row.columns_encoded(Encoding::Windows1252).collect::<Vec<_>>();
+    assert_eq!(cols, vec!["caf\u{e9}", "\u{20ac} 5", "plain"]);
+    assert!(match cols[2] { Cow::Borrowed(_) => true, Cow::Owned(_) => false });
+    let cols = row.columns_encoded(Encoding::Latin1).collect::<Vec<_>>();
+    assert_eq!(cols, vec!["caf\u{e9}", "\u{80} 5", "plain"]);
+}
+
+#[test]
+fn parse_record_public() {
+    use columns::BytesColumns;
+
+    let mut reader = io::BufReader::with_capacity(3, &b"a,\"b\"\"c\"\r\n\nd;e\n\"f"[..]);
+    let (mut buf, mut cols) = (Vec::new(), Vec::new());
+    let config = ParseConfig::default();
+    assert_eq!(parse_record(&mut reader, &mut buf, &mut cols, &config).unwrap(), 10);
+    assert_eq!(BytesColumns::new(&buf, &cols).collect::<Vec<_>>(), vec![&b"a"[..], b"b\"c"]);
+    assert_eq!(parse_record(&mut reader, &mut buf, &mut cols, &config).unwrap(), 1);
+    assert_eq!(cols, vec![0]);
+
+    let config = ParseConfig { delimiter: b';', ..ParseConfig::default() };
+    assert_eq!(parse_record(&mut reader, &mut buf, &mut cols, &config).unwrap(), 4);
+    assert_eq!(BytesColumns::new(&buf, &cols).collect::<Vec<_>>(), vec![&b"d"[..], b"e"]);
+    assert!(parse_record(&mut reader, &mut buf, &mut cols, &config).is_err());
+}
+
+#[test]
+fn trim_trailing_empty() {
+    let data = "a,b,\nc,d,\"\"\ne,f\n,\n";
+    let csv = CsvBuilder::new().trim_trailing_empty(true).flexible(true).from_string(data);
+    let rows = csv.map(|r| r.unwrap().decode::<Vec<String>>().unwrap()).collect::<Vec<_>>();
+    assert_eq!(rows, vec![vec!["a", "b"], vec!["c", "d", ""], vec!["e", "f"], vec![""]]);
+
+    let mut csv = CsvBuilder::new().trim_trailing_empty(true).from_string("a,b,\nc,d\n");
+    assert_eq!(csv.next().unwrap().unwrap().as_bytes(), b"a,b");
+    assert_eq!(csv.next().unwrap().unwrap().len(), 2);
+}
+
+#[test]
+fn iterate_by_mut_ref_after_headers() {
+    // `&mut Csv` is an iterator itself, through `impl Iterator for &mut I`
+    let mut csv = CsvBuilder::new().has_header(true).from_string("h1,h2\n1,2\n3,4\n5,6");
+    assert_eq!(csv.headers(), vec!["h1", "h2"]);
+    let mut rows = Vec::new();
+    for row in &mut csv {
+        let row = row.unwrap().decode::<(u8, u8)>().unwrap();
+        rows.push(row);
+        if rows.len() == 2 {
+            break;
+        }
+    }
+    for row in &mut csv {
+        rows.push(row.unwrap().decode::<(u8, u8)>().unwrap());
+    }
+    assert_eq!(rows, vec![(1, 2), (3, 4), (5, 6)]);
+    assert_eq!(csv.headers(), vec!["h1", "h2"]);
+    assert!(csv.next().is_none());
+}
+
+#[test]
+fn extra_columns() {
+    let data = "h1,h2\na,b,\"c,d\",e\nf,g\n";
+    let read = |extra| {
+        let mut csv = CsvBuilder::new().has_header(true).extra_columns(extra).from_string(data);
+        let first = csv.next().unwrap().map(|r| r.decode::<Vec<String>>().unwrap());
+        if first.is_ok() {
+            let second = csv.next().unwrap().unwrap().decode::<Vec<String>>().unwrap();
+            assert_eq!(second, vec!["f", "g"]);
+        }
+        first
+    };
+    match read(ExtraColumns::Error) {
+        Err(::error::Error::ColumnMismatch { expected: 2, got: 4, line: 2 }) => (),
+        r => panic!("expecting a column mismatch, got {:?}", r),
+    }
+    assert_eq!(read(ExtraColumns::Truncate).unwrap(), vec!["a", "b"]);
+    assert_eq!(read(ExtraColumns::Merge).unwrap(), vec!["a", "b,c,d,e"]);
+
+    let mut csv = CsvBuilder::new().extra_columns(ExtraColumns::Merge).raw_fields(true)
+        .from_string("a,b\nc,\"d\",e\n");
+    assert_eq!(csv.next().unwrap().unwrap().len(), 2);
+    assert_eq!(csv.next().unwrap().unwrap().columns().unwrap().collect::<Vec<_>>(),
+               vec!["c", "\"d\",e"]);
+}
+
+#[test]
+fn columns_as_slice() {
+    let row = Csv::from_string("a,\"b,c\",d,e").next().unwrap().unwrap();
+    let mut cols = row.columns().unwrap();
+    assert_eq!(cols.as_slice(), "a,\"b,c\",d,e");
+    assert_eq!(cols.next(), Some("a"));
+    assert_eq!(cols.next(), Some("b,c"));
+    assert_eq!(cols.as_slice(), "d,e");
+    assert_eq!(cols.nth(1), Some("e"));
+    assert_eq!(cols.as_slice(), "");
+
+    let mut cols = row.bytes_columns();
+    cols.next();
+    assert_eq!(cols.as_slice(), b"\"b,c\",d,e");
+}
+
+#[test]
+fn send_sync() {
+    use columns::{BytesColumns, Columns};
+    use error::Error;
+    use std::fs::File;
+    use std::io::BufReader;
+    use {Records, Row, StringRecord};
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+    assert_send::<Csv<BufReader<File>>>();
+    assert_sync::<Csv<BufReader<File>>>();
+    assert_send::<Csv<&[u8]>>();
+    assert_send::<Records<BufReader<File>>>();
+    assert_send::<Row>();
+    assert_sync::<Row>();
+    assert_send::<StringRecord>();
+    assert_send::<ByteRecord>();
+    assert_send::<Error>();
+    assert_sync::<Error>();
+    assert_sync::<Columns>();
+    assert_sync::<BytesColumns>();
+
+    let csv = CsvBuilder::new().map_fields(|s| s.to_uppercase().into()).build(&b"a,b\nc,d"[..]);
+    let rows = ::std::thread::spawn(move || csv.count()).join().unwrap();
+    assert_eq!(rows, 2);
+}
+
+#[test]
+fn unquote_single_quote_column() {
+    use columns::{BytesColumns, Columns, QuotedColumns};
+
+    let line = "a,\",\"";
+    let cols = [1, 3, 5];
+    assert_eq!(Columns::new(line, &cols).collect::<Vec<_>>(), vec!["a", "\"", "\""]);
+    assert_eq!(BytesColumns::new(line.as_bytes(), &cols).collect::<Vec<_>>(),
+               vec![&b"a"[..], b"\"", b"\""]);
+
+    // no closing quote
+    let line = "\"a,\"\"";
+    let cols = [2, 5];
+    assert_eq!(Columns::new(line, &cols).collect::<Vec<_>>(), vec!["\"a", ""]);
+    assert_eq!(BytesColumns::new(line.as_bytes(), &cols).collect::<Vec<_>>(),
+               vec![&b"\"a"[..], b""]);
+    assert_eq!(QuotedColumns::new(line, &cols).collect::<Vec<_>>(),
+               vec![("\"a", false), ("", true)]);
+}
+
+#[test]
+fn invalid_config() {
+    use error::Error;
+
+    let invalid = |b: CsvBuilder| match b.try_build(&b""[..]) {
+        Err(Error::InvalidConfig(_)) => true,
+        _ => false,
+    };
+    assert!(invalid(CsvBuilder::new().delimiter(b'"')));
+    assert!(!invalid(CsvBuilder::new().delimiter(b'"').quoting(false)));
+    assert!(invalid(CsvBuilder::new().delimiter(b'\n')));
+    assert!(invalid(CsvBuilder::new().delimiter(b'\r').terminator(Terminator::Any)));
+    assert!(!invalid(CsvBuilder::new().delimiter(b'\n').terminator(Terminator::Cr)));
+    assert!(invalid(CsvBuilder::new().delimiter(b';').terminator(Terminator::Bytes(b"|;".to_vec()))));
+    assert!(invalid(CsvBuilder::new().terminator(Terminator::Byte(b'"'))));
+    assert!(!invalid(CsvBuilder::new().tsv()));
+    assert!(invalid(CsvBuilder::new().thousands_separator(b'.')));
+    assert!(invalid(CsvBuilder::new().decimal_separator(b',').thousands_separator(b',')));
+    assert!(!invalid(CsvBuilder::new().decimal_separator(b',').thousands_separator(b'.')));
+    assert!(invalid(CsvBuilder::new().integer_radix(1)));
+    assert!(invalid(CsvBuilder::new().integer_radix(37)));
+    assert!(!invalid(CsvBuilder::new().integer_radix(36)));
+
+    let path = ::std::env::temp_dir().join("quick_csv_invalid_config.csv");
+    ::std::fs::write(&path, "a,b\n").unwrap();
+    assert!(CsvBuilder::new().delimiter(b'\n').from_file(&path).is_err());
+}
+
+#[test]
+#[should_panic(expected = "delimiter is the quote byte")]
+fn invalid_config_panics() {
+    CsvBuilder::new().delimiter(b'"').from_string("a\"b");
+}
+
+#[test]
+#[should_panic(expected = "delimiter is the quote byte")]
+fn invalid_config_deprecated_setter_panics() {
+    Csv::from_string("a\"b").delimiter(b'"');
+}
+
+#[test]
+#[should_panic(expected = "quote byte is part of the line terminator")]
+fn invalid_config_deprecated_terminator_panics() {
+    Csv::from_string("a\"b").terminator(Terminator::Byte(b'"'));
+}
+
+#[test]
+fn valid_config_deprecated_setters() {
+    let csv = Csv::from_string("a\tb").quoting(false).delimiter(b'"').tsv();
+    assert_eq!(csv.get_delimiter(), b'\t');
+}
+
+#[test]
+fn on_progress() {
+    use std::sync::{Arc, Mutex};
+
+    let data = "123456789\n".repeat(10);
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let c = calls.clone();
+    let csv = CsvBuilder::new()
+        .on_progress(25, move |bytes| c.lock().unwrap().push(bytes))
+        .from_string(&data);
+    assert_eq!(csv.count(), 10);
+    assert_eq!(*calls.lock().unwrap(), vec![30, 50, 80, 100]);
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let c = calls.clone();
+    let mut csv = CsvBuilder::new()
+        .on_progress(40, move |bytes| c.lock().unwrap().push(bytes))
+        .from_string(&data);
+    assert_eq!(csv.count_records().unwrap(), 10);
+    assert_eq!(*calls.lock().unwrap(), vec![100]);
+
+    // the callback only needs to be `Send`, not `Sync`
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let calls = ::std::cell::Cell::new(0);
+    let csv = CsvBuilder::new()
+        .on_progress(50, move |bytes| {
+            calls.set(calls.get() + 1);
+            tx.send((calls.get(), bytes)).unwrap();
+        })
+        .from_string(&data);
+    assert_eq!(csv.count(), 10);
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![(1, 50), (2, 100)]);
+}
+
+#[test]
+fn quote_at_buffer_start() {
+    use error::Error;
+
+    let read = |data: &'static [u8]| {
+        Csv::from_reader(io::BufReader::with_capacity(1, data)).flexible(true)
+            .map(|r| r.and_then(|r| r.decode::<Vec<String>>()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(read(b"a,\"b,c\"\n\"d\",e"),
+               vec![Ok(vec!["a".to_string(), "b,c".to_string()]),
+                    Ok(vec!["d".to_string(), "e".to_string()])]);
+    match read(b"a,b\"c\n").pop() {
+        Some(Err(Error::UnexpectedQuote { line_number: 1, .. })) => (),
+        r => panic!("expecting an unexpected quote error, got {:?}", r),
+    }
+}
+
+#[test]
+fn indexed_csv() {
+    use index::{IndexedCsv, RecordIndex};
+
+    let data = "h1,h2\n1,a\n\n2,\"b\nb\"\n3,c\n4,d";
+    let mut csv = CsvBuilder::new().has_header(true).skip_empty_lines(true)
+        .build(io::Cursor::new(data.as_bytes().to_vec()));
+    let index = RecordIndex::build(&mut csv).unwrap();
+    assert_eq!(index.len(), 4);
+    assert_eq!(index.get(1), Some(&Position { byte: 11, line: 2, record: 2 }));
+
+    let mut rows = IndexedCsv::new(csv, index.clone());
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows.csv().header_names(), Some(&["h1".to_string(), "h2".to_string()][..]));
+    let decode = |r: Option<::error::Result<::Row>>| r.unwrap().unwrap().decode::<(u8, String)>().unwrap();
+    assert_eq!(decode(rows.next()), (1, "a".to_string()));
+    assert_eq!(decode(rows.next_back()), (4, "d".to_string()));
+    assert_eq!(rows.len(), 2);
+    assert_eq!(decode(rows.next()), (2, "b\nb".to_string()));
+    let row = rows.next_back().unwrap().unwrap();
+    assert_eq!(row.decode::<(u8, String)>().unwrap(), (3, "c".to_string()));
+    assert!(rows.next().is_none());
+    assert!(rows.next_back().is_none());
+
+    let csv = CsvBuilder::new().has_header(true).skip_empty_lines(true)
+        .build(io::Cursor::new(data.as_bytes().to_vec()));
+    let all = IndexedCsv::new(csv, index).rev()
+        .map(|r| r.unwrap().decode::<(u8, String)>().unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(all, vec![4, 3, 2, 1]);
+}
+
+#[test]
+fn empty_input_vs_blank_line() {
+    let read = |data: &str, skip: bool| {
+        let mut csv = CsvBuilder::new().skip_empty_lines(skip).from_string(data);
+        let rows = (&mut csv).map(|r| r.unwrap().decode::<Vec<String>>().unwrap())
+            .collect::<Vec<_>>();
+        (rows, csv.bytes_read())
+    };
+    let empty: Vec<Vec<String>> = vec![];
+    assert_eq!(read("", false), (empty.clone(), 0));
+    assert_eq!(read("", true), (empty.clone(), 0));
+    assert_eq!(read("\n", false), (vec![vec!["".to_string()]], 1));
+    assert_eq!(read("\n", true), (empty.clone(), 1));
+    assert_eq!(read("\r\n\n", true), (empty.clone(), 3));
+    assert_eq!(read("\"\"\n", true), (vec![vec!["".to_string()]], 3));
+    assert_eq!(read("\u{feff}", false), (empty, 3));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn read_date_format() {
+    use chrono::NaiveDate;
+    let date = NaiveDate::from_ymd_opt(2021, 3, 4).unwrap();
+    let mut csv = CsvBuilder::new()
+        .date_format(0, "%d/%m/%Y")
+        .date_format(2, "%d/%m/%Y %H:%M")
+        .from_string("04/03/2021,2021-03-04,04/03/2021 05:06,2021-03-04T05:06:07.5\n");
+    let row = csv.next().unwrap().unwrap();
+    let mut cols = row.columns().unwrap();
+    assert_eq!(cols.read_naive_date().unwrap(), date);
+    assert_eq!(cols.read_naive_date().unwrap(), date);
+    assert_eq!(cols.read_naive_datetime().unwrap(), date.and_hms_opt(5, 6, 0).unwrap());
+    assert_eq!(cols.read_naive_datetime().unwrap(), date.and_hms_milli_opt(5, 6, 7, 500).unwrap());
+
+    let mut csv = CsvBuilder::new().date_format(0, "%d/%m/%Y").from_string("2021-03-04\n");
+    match csv.next().unwrap().unwrap().columns().unwrap().read_naive_date() {
+        Err(::error::Error::ParseField { column: 0, ref value, .. }) => assert_eq!(value, "2021-03-04"),
+        r => panic!("expected a ParseField error, got {:?}", r),
+    }
+    let strings = CsvBuilder::new().date_format(0, "%d/%m/%Y").from_string("04/03/2021\n")
+        .map(|r| r.unwrap().decode::<(String,)>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(strings, vec![("04/03/2021".to_string(),)]);
+}
+
+#[test]
+fn row_utf8_validated_once() {
+    let mut csv = Csv::from_reader(&b"a,b\n\xff,c\n"[..]);
+    let row = csv.next().unwrap().unwrap();
+    for _ in 0..2 {
+        assert_eq!(row.columns().unwrap().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(row.decode::<(String, String)>().unwrap(), ("a".to_string(), "b".to_string()));
+    }
+    assert_eq!(row.clone().as_str().unwrap(), "a,b");
+
+    let row = csv.next().unwrap().unwrap();
+    for _ in 0..2 {
+        let err = row.columns().err().unwrap().to_string();
+        assert!(err.contains("at byte 0 of line 2"), "{}", err);
+        assert_eq!(row.bytes_columns().collect::<Vec<_>>(), vec![&b"\xff"[..], &b"c"[..]]);
+    }
+}
+
+#[test]
+fn newline_in_quotes() {
+    let data = "\"line1\r\nline2\",\"a\r\nb\r\",c\r\nd,e,f\r\n";
+    let read = |newline: NewlineInQuotes, capacity: usize| {
+        CsvBuilder::new()
+            .newline_in_quotes(newline)
+            .build(io::BufReader::with_capacity(capacity, data.as_bytes()))
+            .map(|r| r.unwrap().decode::<(String, String, String)>().unwrap())
+            .collect::<Vec<_>>()
+    };
+    let row = |a: &str, b: &str, c: &str| (a.to_string(), b.to_string(), c.to_string());
+    for &capacity in &[1, 2, 8192] {
+        assert_eq!(read(NewlineInQuotes::Preserve, capacity),
+                   vec![row("line1\r\nline2", "a\r\nb\r", "c"), row("d", "e", "f")]);
+        assert_eq!(read(NewlineInQuotes::NormalizeLf, capacity),
+                   vec![row("line1\nline2", "a\nb\r", "c"), row("d", "e", "f")]);
+    }
+
+    let (mut buf, mut cols) = (Vec::new(), Vec::new());
+    let config = ParseConfig { newline_in_quotes: NewlineInQuotes::NormalizeLf, ..ParseConfig::default() };
+    parse_record(&mut "\"x\r\ny\",z\r\n".as_bytes(), &mut buf, &mut cols, &config).unwrap();
+    assert_eq!(buf, b"\"x\ny\",z");
+    assert_eq!(cols, vec![5, 7]);
+}
+
+#[test]
+fn into_deserialize() {
+    let rows = CsvBuilder::new()
+        .has_header(true)
+        .from_string("name,age\nbob,3\nann,x\n")
+        .into_deserialize::<(String, u8)>()
+        .collect::<Vec<_>>();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].as_ref().unwrap(), &("bob".to_string(), 3));
+    assert!(rows[1].is_err());
+}
+
+#[test]
+fn next_borrowed() {
+    let mut csv = CsvBuilder::new().has_header(true).skip_empty_lines(true)
+        .from_string("h1,h2\na,\"b,c\"\n\nd,e\n");
+    let mut rows = Vec::new();
+    while let Some(record) = csv.next_borrowed() {
+        let record = record.unwrap();
+        assert_eq!(record.len(), 2);
+        rows.push(record.iter().map(|c| c.to_vec()).collect::<Vec<_>>());
+    }
+    assert_eq!(rows, vec![vec![b"a".to_vec(), b"b,c".to_vec()], vec![b"d".to_vec(), b"e".to_vec()]]);
+    assert_eq!(csv.headers(), vec!["h1".to_string(), "h2".to_string()]);
+
+    let mut csv = Csv::from_string("a,b\nc\n");
+    assert_eq!(csv.next_borrowed().unwrap().unwrap().get(1), Some(&b"b"[..]));
+    assert!(csv.next_borrowed().unwrap().is_err());
+    assert!(csv.next_borrowed().is_none());
+}
+
+#[test]
+fn escaped_quote_across_buffers() {
+    let read = |data: &'static str, raw: bool| {
+        CsvBuilder::new()
+            .raw_fields(raw)
+            .flexible(true)
+            .build(io::BufReader::with_capacity(1, data.as_bytes()))
+            .map(|r| r.map(|r| r.bytes_columns().map(|c| c.to_vec()).collect::<Vec<_>>()))
+            .collect::<Vec<_>>()
+    };
+    let rows = read("\"a\"\"b\",c\n\"\"\"\"\n", false);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].as_ref().unwrap(), &vec![b"a\"b".to_vec(), b"c".to_vec()]);
+    assert_eq!(rows[1].as_ref().unwrap(), &vec![b"\"".to_vec()]);
+    let rows = read("\"a\"\"b\",c\n", true);
+    assert_eq!(rows[0].as_ref().unwrap(), &vec![b"\"a\"\"b\"".to_vec(), b"c".to_vec()]);
+
+    match read("\"a\"b\n", false).remove(0) {
+        Err(::error::Error::UnescapedQuote { line, pos, .. }) => {
+            assert_eq!(line, b"\"a\"b".to_vec());
+            assert_eq!(pos, 2);
+        }
+        r => panic!("expected an UnescapedQuote error, got {:?}", r),
+    }
+}
+
+#[test]
+fn terminator_auto() {
+    let read = |data: &'static str| {
+        CsvBuilder::new()
+            .terminator(Terminator::Auto)
+            .flexible(true)
+            .from_string(data)
+            .map(|r| r.unwrap().decode::<Vec<String>>().unwrap())
+            .collect::<Vec<_>>()
+    };
+    let row = |cols: &[&str]| cols.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+    let expected = vec![row(&["a", "b"]), row(&["c", "d"])];
+    assert_eq!(read("a,b\r\nc,d\r\n"), expected);
+    assert_eq!(read("a,b\nc,d\n"), expected);
+    assert_eq!(read("a,b\rc,d\r"), expected);
+    assert_eq!(read("\u{feff}a,b\rc,d"), expected);
+
+    // the detected terminator is used for the whole file
+    assert_eq!(read("a,b\r\nc\rd\r\ne\n"), vec![row(&["a", "b"]), row(&["c\rd"]), row(&["e"])]);
+    assert_eq!(read("a,b\nc\r\nd\re\n"), vec![row(&["a", "b"]), row(&["c\r"]), row(&["d\re"])]);
+    assert_eq!(read("a,b\rc\nd\re\r\n"), vec![row(&["a", "b"]), row(&["c\nd"]), row(&["e"]),
+                                                row(&["\n"])]);
+    assert_eq!(CsvBuilder::new().terminator(Terminator::Auto).from_string("a\rb\nc\r\n")
+                   .count_records().unwrap(), 3);
+}
+
+#[test]
+fn terminator_auto_small_buffer() {
+    // only the first reader buffer is looked at, `Any` being used without line end
+    let read = |data: &'static [u8], cap: usize, terminator: Terminator| {
+        let mut csv = CsvBuilder::new().terminator(Terminator::Auto).flexible(true)
+            .build(io::BufReader::with_capacity(cap, data));
+        let rows = csv.by_ref().map(|r| r.unwrap().decode::<Vec<String>>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(*csv.get_terminator(), terminator, "capacity {}", cap);
+        rows
+    };
+    assert_eq!(read(b",\ra\na,", 64, Terminator::Cr), vec![vec!["", ""], vec!["a\na", ""]]);
+    assert_eq!(read(b",\ra\na,", 1, Terminator::Any),
+               vec![vec!["", ""], vec!["a"], vec!["a", ""]]);
+    assert_eq!(read(b"|\n\n\r|", 64, Terminator::Byte(b'\n')),
+               vec![vec!["|"], vec![""], vec!["\r|"]]);
+    assert_eq!(read(b"|\n\n\r|", 1, Terminator::Any),
+               vec![vec!["|"], vec![""], vec![""], vec!["|"]]);
+}
+
+#[test]
+fn terminator_auto_reset() {
+    let data = "a,b\r\nc,d\r\n".as_bytes().to_vec();
+    let mut csv = CsvBuilder::new().terminator(Terminator::Auto).build(io::Cursor::new(data));
+    assert_eq!(csv.by_ref().count(), 2);
+    assert_eq!(*csv.get_terminator(), Terminator::Crlf);
+    csv.reset().unwrap();
+    assert_eq!(*csv.get_terminator(), Terminator::Auto);
+    assert_eq!(csv.by_ref().count(), 2);
+    assert_eq!(*csv.get_terminator(), Terminator::Crlf);
+}
+
+#[test]
+fn from_files() {
+    use multi::MultiFileReader;
+
+    let dir = ::std::env::temp_dir();
+    let paths = ["empty", "01", "02", "03"].iter()
+        .map(|m| dir.join(format!("quick_csv_from_files_{}.csv", m)))
+        .collect::<Vec<_>>();
+    ::std::fs::write(&paths[0], "").unwrap();
+    ::std::fs::write(&paths[1], "h1,h2\na,1\nb,2").unwrap();
+    ::std::fs::write(&paths[2], "\u{feff}h1,h2\r\nc,3\r\n").unwrap();
+    ::std::fs::write(&paths[3], "h1,h2\nd,4\n").unwrap();
+
+    let mut csv = CsvBuilder::new().has_header(true).from_files(&paths).unwrap();
+    let rows = (&mut csv).map(|r| r.unwrap().decode::<(String, u8)>().unwrap())
+        .map(|(s, n)| format!("{}{}", s, n))
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec!["a1", "b2", "c3", "d4"]);
+    assert_eq!(csv.headers(), vec!["h1".to_string(), "h2".to_string()]);
+    assert_eq!(csv.current_line(), 5);
+
+    let rows = Csv::from_files(&paths).unwrap()
+        .map(|r| r.unwrap().decode::<(String, String)>().unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec!["h1", "a", "b", "h1", "c", "h1", "d"]);
+
+    let mut reader = MultiFileReader::new(&paths[1..3], true);
+    let mut content = String::new();
+    reader.read_to_string(&mut content).unwrap();
+    assert_eq!(content, "h1,h2\na,1\nb,2\nc,3\r\n");
+    assert!(reader.current_path().is_none());
+
+    assert!(Csv::from_files(&[dir.join("quick_csv_from_files_missing.csv")]).is_err());
+}
+
+#[test]
+fn from_files_bytes_terminator() {
+    let dir = ::std::env::temp_dir();
+    let paths = ["01", "02", "03"].iter()
+        .map(|m| dir.join(format!("quick_csv_from_files_bytes_{}.csv", m)))
+        .collect::<Vec<_>>();
+    // the first file ends with a byte of the sequence, not with the sequence
+    ::std::fs::write(&paths[0], "a<=>b=").unwrap();
+    ::std::fs::write(&paths[1], "c<=>").unwrap();
+    ::std::fs::write(&paths[2], "d").unwrap();
+
+    let rows = CsvBuilder::new().terminator(Terminator::Bytes(b"<=>".to_vec()))
+        .from_files(&paths).unwrap()
+        .map(|r| r.unwrap().decode::<(String,)>().unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec!["a", "b=", "c", "d"]);
+}
+
+#[test]
+fn from_files_size_hint() {
+    let dir = ::std::env::temp_dir();
+    let paths = ["01", "02"].iter()
+        .map(|m| dir.join(format!("quick_csv_from_files_size_hint_{}.csv", m)))
+        .collect::<Vec<_>>();
+    ::std::fs::write(&paths[0], "a").unwrap();
+    ::std::fs::write(&paths[1], "b").unwrap();
+
+    // the line terminator inserted after the first file is read as well
+    let mut csv = Csv::from_files(&paths).unwrap();
+    for left in (0..2).rev() {
+        assert!(csv.next().unwrap().is_ok());
+        assert!(csv.size_hint().1.unwrap() >= left);
+    }
+    assert!(csv.next().is_none());
+}
+
+#[test]
+fn validate_schema() {
+    use schema::{ColumnType, FieldType};
+    let data = "id,score,ok\n1,2,true\n2,2.5,\n3,4,false\n".as_bytes().to_vec();
+    let mut csv = CsvBuilder::new().has_header(true).build(io::Cursor::new(data));
+    let schema = csv.infer_schema(2).unwrap();
+    assert_eq!(csv.validate_schema(&schema), Ok(()));
+
+    let t = |field_type, nullable| ColumnType { field_type: field_type, nullable: nullable };
+    let read = |data: &'static str| CsvBuilder::new().has_header(true).flexible(true).from_string(data);
+    let schema = vec![t(FieldType::Integer, false), t(FieldType::Float, true)];
+    assert_eq!(read("a,b\n1,2\n2,\n3\n").validate_schema(&schema), Ok(()));
+    let err = |data| read(data).validate_schema(&schema).err().unwrap().to_string();
+    assert_eq!(err("a,b\n1,2\n2,x\n"), "Invalid column 1 at line 3: expected Float or null, found 'x'");
+    assert_eq!(err("a,b\n1,2\n,1\n"), "Invalid column 0 at line 3: expected Integer, found ''");
+    assert_eq!(err("a,b\n1.5,2\n"), "Invalid column 0 at line 2: expected Integer, found '1.5'");
+    assert_eq!(err("a,b\n1,2,3\n"), "Invalid column 2 at line 2: expected 2 columns, found '3'");
+    let schema = vec![t(FieldType::String, false), t(FieldType::Bool, false)];
+    assert_eq!(read("a,b\n1,true\nx\n").validate_schema(&schema).err().unwrap().to_string(),
+               "Invalid column 1 at line 3: expected Bool, found a missing column");
+}
+
+#[test]
+fn column_ranges() {
+    let row = Csv::from_string("a,\"b,c\",d\n").next().unwrap().unwrap();
+    let ranges = row.column_ranges();
+    assert_eq!(ranges, vec![0..1, 2..7, 8..9]);
+    assert_eq!(&row.as_bytes()[ranges[1].clone()], b"\"b,c\"");
+
+    let row = Csv::from_string(",\n").next().unwrap().unwrap();
+    assert_eq!(row.column_ranges(), vec![0..0, 1..1]);
+
+    let data = "\"a\"\"b\",c\n";
+    let row = Csv::from_string(data).next().unwrap().unwrap();
+    assert_eq!(row.column_ranges(), vec![0..5, 6..7]);
+    let row = CsvBuilder::new().raw_fields(true).from_string(data).next().unwrap().unwrap();
+    assert_eq!(row.column_ranges(), vec![0..6, 7..8]);
+}
+
+#[test]
+fn utf8_mode() {
+    let data = &b"x,y\na\xffb,c\n\"d\xfe\",e\n"[..];
+    let read = |mode: Utf8Mode| {
+        let mut csv = CsvBuilder::new().utf8(mode).build(data);
+        let rows = (&mut csv).map(|r| r.unwrap().decode::<(String, String)>())
+            .collect::<Vec<_>>();
+        (rows, csv.current_line())
+    };
+    let row = |a: &str, b: &str| Ok((a.to_string(), b.to_string()));
+
+    let (rows, lines) = read(Utf8Mode::Strict);
+    assert_eq!(rows[0], row("x", "y"));
+    assert!(rows[1].is_err() && rows[2].is_err());
+    assert_eq!(lines, 3);
+
+    let (rows, lines) = read(Utf8Mode::Lossy);
+    assert_eq!(rows, vec![row("x", "y"), row("a\u{fffd}b", "c"), row("d\u{fffd}", "e")]);
+    assert_eq!(lines, 3);
+
+    let (rows, lines) = read(Utf8Mode::SkipRow);
+    assert_eq!(rows, vec![row("x", "y")]);
+    assert_eq!(lines, 3);
+
+    let mut csv = CsvBuilder::new().utf8(Utf8Mode::Lossy).build(data);
+    let mut record = ByteRecord::new();
+    csv.read_byte_record(&mut record).unwrap();
+    csv.read_byte_record(&mut record).unwrap();
+    assert_eq!(record.get(0), Some("a\u{fffd}b".as_bytes()));
+}
+
+#[test]
+fn utf8_skip_row_nth() {
+    let data = &b"b\xffc\na\nd\n"[..];
+    let mut csv = CsvBuilder::new().utf8(Utf8Mode::SkipRow).build(data);
+    csv.next();
+    let next = csv.next().unwrap().unwrap().decode::<String>().unwrap();
+    let mut csv = CsvBuilder::new().utf8(Utf8Mode::SkipRow).build(data);
+    let nth = csv.nth(1).unwrap().unwrap().decode::<String>().unwrap();
+    assert_eq!(next, "d");
+    assert_eq!(nth, next);
+    let mut csv = CsvBuilder::new().utf8(Utf8Mode::SkipRow).build(data);
+    assert_eq!(csv.by_ref().skip(1).count(), 1);
+}
+
+#[test]
+fn decode_whitespace_strings() {
+    let row = Csv::from_string("  ,\t, x ").next().unwrap().unwrap();
+    assert_eq!(row.decode::<(String, String, String)>().unwrap(),
+               ("  ".to_string(), "\t".to_string(), " x ".to_string()));
+}
+
+#[test]
+fn skip_bytes() {
+    let read = |data: &'static str, n: u64| {
+        let mut csv = CsvBuilder::new().skip_bytes(n).from_string(data);
+        let rows = (&mut csv).map(|r| r.unwrap().decode::<Vec<String>>().unwrap())
+            .collect::<Vec<_>>();
+        (rows, csv.bytes_read())
+    };
+    let rows = vec![vec!["a".to_string(), "b".to_string()], vec!["c".to_string(), "d".to_string()]];
+    assert_eq!(read("\x01\n,\"xa,b\nc,d", 5), (rows.clone(), 12));
+    assert_eq!(read("12345\u{feff}a,b\nc,d", 5), (rows.clone(), 15));
+    assert_eq!(read("123", 5), (vec![], 3));
+
+    let data = "#####h1,h2\na,b\nc,d\n".as_bytes().to_vec();
+    let mut csv = CsvBuilder::new().skip_bytes(5).has_header(true).build(io::Cursor::new(data));
+    assert_eq!(csv.by_ref().count(), 2);
+    csv.reset().unwrap();
+    assert_eq!(csv.headers(), vec!["h1".to_string(), "h2".to_string()]);
+    assert_eq!(csv.map(|r| r.unwrap().decode::<Vec<String>>().unwrap()).collect::<Vec<_>>(), rows);
+}
+
+#[test]
+fn clone_columns() {
+    let row = Csv::from_string("a,\"b\",c,d").next().unwrap().unwrap();
+    let mut columns = row.columns().unwrap();
+    columns.next();
+    let mut clone = columns.clone();
+    assert_eq!(columns.collect::<Vec<_>>(), vec!["b", "c", "d"]);
+    assert_eq!(clone.next(), Some("b"));
+    assert_eq!(clone.decode::<(String, String)>().unwrap(), ("c".to_string(), "d".to_string()));
+
+    let mut bytes = row.bytes_columns();
+    bytes.next();
+    assert_eq!(bytes.clone().count(), 3);
+    assert_eq!(bytes.next(), Some(&b"b"[..]));
+}
+
+#[test]
+fn delimiter_char() {
+    let rows = CsvBuilder::new().delimiter_char('\t').from_string("a\tb,c\n")
+        .map(|r| r.unwrap().decode::<(String, String)>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![("a".to_string(), "b,c".to_string())]);
+
+    let builder = CsvBuilder::new().delimiter_char('\u{20ac}');
+    match builder.validate() {
+        Err(::error::Error::InvalidConfig(msg)) => assert!(msg.contains('\u{20ac}'), "{}", msg),
+        r => panic!("expected an InvalidConfig error, got {:?}", r),
+    }
+    assert!(CsvBuilder::new().delimiter_char('\u{20ac}').try_build(&b""[..]).is_err());
+    assert!(CsvBuilder::new().delimiter_char('\u{20ac}').delimiter(b';').validate().is_ok());
+}
+
+/// Reader returning 2 bytes at a time and failing once at `fail_at`
+struct FlakyReader {
+    inner: io::Cursor<Vec<u8>>,
+    fail_at: Option<u64>,
+}
+
+impl Read for FlakyReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let available = try!(self.fill_buf());
+            let n = ::std::cmp::min(buf.len(), available.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for FlakyReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self.fail_at {
+            Some(pos) if self.inner.position() >= pos => {
+                self.fail_at = None;
+                Err(io::Error::new(io::ErrorKind::Other, "transient error"))
+            }
+            _ => {
+                let available = try!(self.inner.fill_buf());
+                Ok(&available[..::std::cmp::min(available.len(), 2)])
+            }
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+    }
+}
+
+impl Seek for FlakyReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn clear_error() {
+    let reader = FlakyReader {
+        inner: io::Cursor::new(b"1,2\n3,4\n5,6\n".to_vec()),
+        fail_at: Some(6),
+    };
+    let mut csv = Csv::from_reader(reader);
+    assert_eq!(csv.next().unwrap().unwrap().decode::<(u8, u8)>().unwrap(), (1, 2));
+    let position = csv.position();
+    match csv.next() {
+        Some(Err(::error::Error::Io(_))) => (),
+        r => panic!("expected an io error, got {:?}", r.map(|r| r.map(|r| r.len()))),
+    }
+    assert!(csv.next().is_none());
+
+    csv.clear_error();
+    csv.seek_to(&position).unwrap();
+    let rows = csv.map(|r| r.unwrap().decode::<(u8, u8)>().unwrap()).collect::<Vec<_>>();
+    assert_eq!(rows, vec![(3, 4), (5, 6)]);
+}
@@ -0,0 +1,114 @@
+//! Csv writer module
+//!
+//! Provides `CsvWriter` to serialize rows back into csv, using the same
+//! quoting rules as the reader
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use error::Result;
+
+/// Csv writer
+///
+/// Columns are only quoted when they contain the delimiter, the quote,
+/// `\r` or `\n`
+///
+/// # Example
+///
+/// ```rust
+/// let mut wtr = quick_csv::writer::CsvWriter::from_writer(Vec::new());
+/// wtr.write_record(&["a", "b,c"]).unwrap();
+/// assert_eq!(wtr.into_inner(), b"a,\"b,c\"\n");
+/// ```
+pub struct CsvWriter<W: Write> {
+    /// delimiter
+    delimiter: u8,
+    /// quote
+    quote: u8,
+    /// writer
+    writer: W,
+}
+
+impl<W: Write> CsvWriter<W> {
+
+    /// Creates a CsvWriter from a generic Writer
+    ///
+    /// Note: default delimiter = ',' and default quote = '"'
+    pub fn from_writer(writer: W) -> CsvWriter<W> {
+        CsvWriter {
+            delimiter: b',',
+            quote: b'"',
+            writer: writer,
+        }
+    }
+
+    /// Sets a new delimiter
+    pub fn delimiter(mut self, delimiter: u8) -> CsvWriter<W> {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets a new quote
+    pub fn quote(mut self, quote: u8) -> CsvWriter<W> {
+        self.quote = quote;
+        self
+    }
+
+    /// Writes a record, terminated by `\n`
+    pub fn write_record<I, S>(&mut self, fields: I) -> Result<()>
+        where I: IntoIterator<Item = S>,
+              S: AsRef<[u8]>
+    {
+        for (i, field) in fields.into_iter().enumerate() {
+            if i > 0 {
+                try!(self.writer.write_all(&[self.delimiter]));
+            }
+            try!(self.write_field(field.as_ref()));
+        }
+        try!(self.writer.write_all(b"\n"));
+        Ok(())
+    }
+
+    /// Flushes the underlying writer
+    pub fn flush(&mut self) -> Result<()> {
+        try!(self.writer.flush());
+        Ok(())
+    }
+
+    /// Unwraps the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_field(&mut self, field: &[u8]) -> Result<()> {
+        let (delimiter, quote) = (self.delimiter, self.quote);
+        if !field.iter().any(|&b| b == delimiter || b == quote || b == b'\r' || b == b'\n') {
+            try!(self.writer.write_all(field));
+            return Ok(());
+        }
+        try!(self.writer.write_all(&[quote]));
+        let mut start = 0;
+        for (i, &b) in field.iter().enumerate() {
+            if b == quote {
+                // escape quote by doubling it
+                try!(self.writer.write_all(&field[start..i + 1]));
+                try!(self.writer.write_all(&[quote]));
+                start = i + 1;
+            }
+        }
+        try!(self.writer.write_all(&field[start..]));
+        try!(self.writer.write_all(&[quote]));
+        Ok(())
+    }
+
+}
+
+impl CsvWriter<BufWriter<File>> {
+    /// Creates a csv writer to a file path, truncating any existing file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<CsvWriter<BufWriter<File>>>
+    {
+        let writer = BufWriter::new(try!(File::create(path)));
+        Ok(CsvWriter::from_writer(writer))
+    }
+}